@@ -7,6 +7,39 @@ pub const ADMIN_WHITELIST: &[Pubkey] = &[
 
 pub const MAX_FEE: u16 = 10_000;
 
+/* NAV bounds in basis points */
+pub const DEFAULT_WITHDRAW_NAV_TOLERANCE: u16 = 100;
+pub const DEFAULT_MAX_NAV_CHANGE: u16 = 5;
+
+/* Signed withdraw messages */
+pub const WITHDRAW_MESSAGE_DOMAIN: &[u8] = b"SolvBTC Withdraw";
+
+/* BIP-340 signed withdraw messages */
+pub const BIP340_CHALLENGE_TAG: &[u8] = b"BIP0340/challenge";
+
+/* EIP-712 signed withdraw messages */
+pub const EIP712_DOMAIN_VERSION: &[u8] = b"2";
+pub const EIP712_DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,uint256 chainId,bytes32 salt)";
+pub const EIP712_REQUEST_TYPE: &[u8] = b"WithdrawRequest(bytes32 vault,bytes32 user,bytes32 withdrawToken,bytes32 withdrawTokenAccount,bytes32 token,uint64 sequence,uint64 shares,uint64 nav,uint64 withdrawAmount)";
+pub const EIP712_FILL_TYPE: &[u8] = b"WithdrawFill(bytes32 request,uint64 filledAmount,uint64 amount)";
+pub const EIP712_SWITCH_TYPE: &[u8] = b"WithdrawSwitch(bytes32 request,uint64 filledAmount,bytes32 withdrawToken,bytes32 withdrawTokenAccount)";
+/// Order of the secp256k1 group
+pub const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+/// Half the order of secp256k1, the largest s accepted in a signature
+pub const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/* Pause flags */
+pub const PAUSE_DEPOSIT: u8 = 1 << 0;
+pub const PAUSE_WITHDRAW_REQUEST: u8 = 1 << 1;
+pub const PAUSE_WITHDRAW: u8 = 1 << 2;
+pub const PAUSE_MINT: u8 = 1 << 3;
+
 /* Mints */
 // pub const SOLV_MINT: ([u8; 32], u8) =
 //     const_crypto::ed25519::derive_program_address(&[b"solv"], crate::ID_CONST.as_array());
@@ -0,0 +1,45 @@
+use crate::{helpers::migrate_account, state::MinterManager};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct MinterManagerMigrate<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Separate authority from payer to support multisig and PDA signers
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This PDA is one of the signers in the multisig
+    pub vault: AccountInfo<'info>,
+    /// The minter manager in its previous layout, checked and deserialized once it is resized
+    /// CHECK: Owner, discriminator and admin are validated in migrate
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"minter_manager", vault.key().as_ref()],
+        bump
+    )]
+    pub minter_manager: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MinterManagerMigrate<'info> {
+    /// Fields appended since the minter manager was created default to zero: no guardian and not paused
+    pub fn migrate(&mut self) -> Result<()> {
+        migrate_account(
+            &self.minter_manager.to_account_info(),
+            &self.payer.to_account_info(),
+            &self.system_program.to_account_info(),
+            MinterManager::DISCRIMINATOR,
+            MinterManager::DISCRIMINATOR.len() + MinterManager::INIT_SPACE,
+        )?;
+
+        let minter_manager = MinterManager::try_deserialize(&mut &self.minter_manager.try_borrow_data()?[..])?;
+        require_keys_eq!(minter_manager.admin, self.admin.key(), ErrorCode::ConstraintHasOne);
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::{helpers::{mint_to_checked_1_of_n_multisig, MintToChecked1ofNMultisig}, state::MinterManager};
+use crate::{constants::PAUSE_MINT, errors::SolvError, helpers::{mint_to_checked_1_of_n_multisig, MintToChecked1ofNMultisig}, state::MinterManager};
 use anchor_spl::token_interface::{
     Mint, TokenAccount, TokenInterface,
 };
@@ -30,6 +30,7 @@ pub struct MinterManagerMint<'info> {
     // MinterManager
     #[account(
         constraint = minter_manager.minters.contains(&authority.key()),
+        constraint = !minter_manager.is_paused(PAUSE_MINT) @ SolvError::MinterPaused,
         seeds = [b"minter_manager", vault.key().as_ref()],
         bump = minter_manager.bump
     )]
@@ -0,0 +1,29 @@
+use crate::{errors::SolvError, events::PausedStateChanged, state::MinterManager};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct MinterManagerPause<'info> {
+    /// Either the minter manager admin or guardian
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = authority.key().eq(&minter_manager.admin) || authority.key().eq(&minter_manager.guardian) @ SolvError::InvalidAddress
+    )]
+    pub minter_manager: Account<'info, MinterManager>,
+}
+
+impl<'info> MinterManagerPause<'info> {
+    pub fn set_paused(&mut self, paused: u8) -> Result<()> {
+        let previous = self.minter_manager.paused;
+        self.minter_manager.set_paused(&self.authority.key(), paused)?;
+
+        emit!(PausedStateChanged {
+            account: self.minter_manager.key(),
+            authority: self.authority.key(),
+            previous,
+            paused,
+        });
+
+        Ok(())
+    }
+}
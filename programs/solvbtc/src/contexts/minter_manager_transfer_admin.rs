@@ -18,4 +18,8 @@ impl<'info> MinterManagerTransferAdmin<'info> {
     pub fn transfer_admin(&mut self, admin: Pubkey) -> Result<()> {
         self.minter_manager.transfer_admin(admin)
     }
+
+    pub fn set_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        self.minter_manager.set_guardian(guardian)
+    }
 }
@@ -5,18 +5,57 @@ pub use vault_initialize::*;
 pub mod vault_update;
 pub use vault_update::*;
 
+pub mod vault_add_currency;
+pub use vault_add_currency::*;
+
+pub mod vault_migrate;
+pub use vault_migrate::*;
+
 pub mod vault_deposit;
 pub use vault_deposit::*;
 
 pub mod vault_request_withdraw;
 pub use vault_request_withdraw::*;
 
+pub mod vault_instant_redeem;
+pub use vault_instant_redeem::*;
+
+pub mod vault_update_address_book;
+pub use vault_update_address_book::*;
+
+pub mod vault_update_verifier_set;
+pub use vault_update_verifier_set::*;
+
 pub mod vault_withdraw;
 pub use vault_withdraw::*;
 
+pub mod vault_withdraw_for;
+pub use vault_withdraw_for::*;
+
+pub mod vault_withdraw_batch;
+pub use vault_withdraw_batch::*;
+
+pub mod vault_switch_withdraw_currency;
+pub use vault_switch_withdraw_currency::*;
+
+pub mod vault_cancel_withdraw_request;
+pub use vault_cancel_withdraw_request::*;
+
+pub mod vault_expire_withdraw_request;
+pub use vault_expire_withdraw_request::*;
+
+pub mod vault_advance_withdraw_queue;
+pub use vault_advance_withdraw_queue::*;
+
+pub mod vault_close_redemption_epoch;
+pub use vault_close_redemption_epoch::*;
+
 pub mod vault_oracle_update;
 pub use vault_oracle_update::*;
 
+pub mod vault_pause;
+pub use vault_pause::*;
+
 // Minter Manager Contexts
 pub mod minter_manager_initialize;
 pub use minter_manager_initialize::*;
@@ -28,4 +67,10 @@ pub mod minter_manager_transfer_admin;
 pub use minter_manager_transfer_admin::*;
 
 pub use minter_manager_mint::*;
-pub mod minter_manager_mint;
\ No newline at end of file
+pub mod minter_manager_mint;
+
+pub mod minter_manager_pause;
+pub use minter_manager_pause::*;
+
+pub mod minter_manager_migrate;
+pub use minter_manager_migrate::*;
@@ -0,0 +1,28 @@
+use crate::state::Vault;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct VaultAddCurrency<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Separate authority from payer to support multisig and PDA signers
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// The deposit currency, read to record its decimals
+    pub currency: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultAddCurrency<'info> {
+    pub fn add_currency(&mut self, deposit_fee: u16, rate: u64) -> Result<()> {
+        self.vault.add_currency(self.currency.key(), self.currency.decimals, deposit_fee, rate)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{errors::SolvError, events::WithdrawQueueAdvanceEvent, state::Vault};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct VaultAdvanceWithdrawQueue<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.mint.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+impl<'info> VaultAdvanceWithdrawQueue<'info> {
+    /// Move the queue head past completed withdraw requests, passed in sequence order
+    /// starting at the current head as remaining accounts
+    pub fn advance_queue(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let vault = self.vault.key();
+        let mut head = self.vault.withdraw_queue_head;
+
+        for withdraw_request in remaining_accounts {
+            let (address, _) = Pubkey::find_program_address(
+                &[b"withdraw_request", vault.as_ref(), head.to_le_bytes().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(withdraw_request.key(), address, SolvError::InvalidSequence);
+
+            // Completed requests are kept as empty accounts owned by the program
+            require_keys_eq!(*withdraw_request.owner, crate::ID, SolvError::WithdrawRequestPending);
+            require!(withdraw_request.data_is_empty(), SolvError::WithdrawRequestPending);
+
+            head = head.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+
+        self.vault.withdraw_queue_head = head;
+
+        emit!(WithdrawQueueAdvanceEvent { vault, head });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    constants::PAUSE_WITHDRAW_REQUEST,
+    errors::SolvError,
+    events::WithdrawCancelEvent,
+    helpers::{close_request_account, load_request_account, mint_from_vault},
+    state::Vault,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+#[instruction(sequence: u64)]
+pub struct VaultCancelWithdrawRequest<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [
+            b"withdraw_request", 
+            vault.key().as_ref(),
+            sequence.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    /// CHECK: We manually deserialize this in the function body
+    pub withdraw_request: AccountInfo<'info>,
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = mint_target
+    )]
+    pub user_target_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The multisig account that serves as mint authority
+    /// CHECK: This is validated as a multisig account by the token program
+    pub multisig: AccountInfo<'info>,
+    pub mint_withdraw: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub mint_target: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"vault", mint_target.key().as_ref()],
+        bump = vault.bump,
+        constraint = !vault.is_paused(PAUSE_WITHDRAW_REQUEST) @ SolvError::VaultPaused,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> VaultCancelWithdrawRequest<'info> {
+    pub fn cancel_request(&mut self) -> Result<()> {
+        // Get withdraw request
+        let withdraw_request = load_request_account(&self.withdraw_request)?;
+        withdraw_request.check_owner(&self.user.key(), &self.mint_withdraw.key())?;
+
+        // Ensure the cancel delay has elapsed
+        let cancellable_at = withdraw_request
+            .created_at
+            .checked_add(self.vault.cancel_delay.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        require_gte!(Clock::get()?.unix_timestamp, cancellable_at, SolvError::CancelDelayNotElapsed);
+
+        // Only shares of the amount not yet filled are restored
+        let shares = withdraw_request.unfilled_shares()?;
+        mint_from_vault(
+            self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_target,
+            self.multisig.to_account_info(),
+            self.user_target_ta.to_account_info(),
+            shares,
+        )?;
+
+        emit!(WithdrawCancelEvent {
+            user: withdraw_request.user,
+            vault: self.vault.key(),
+            withdraw_token: withdraw_request.withdraw_token,
+            token: withdraw_request.token,
+            shares,
+            sequence: withdraw_request.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Resize the withdraw_request account to zero bytes and refund Rent to user
+    pub fn close_request_account(&mut self) -> Result<()> {
+        close_request_account(&self.withdraw_request, &self.user.to_account_info())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{
+    errors::SolvError,
+    events::RedemptionEpochCloseEvent,
+    state::{RedemptionEpoch, Vault},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct VaultCloseRedemptionEpoch<'info> {
+    #[account(mut)]
+    pub oracle_manager: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.mint.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.oracle_manager.eq(&oracle_manager.key()) @ SolvError::InvalidAddress,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = oracle_manager,
+        space = RedemptionEpoch::DISCRIMINATOR.len() + RedemptionEpoch::INIT_SPACE,
+        seeds = [
+            b"redemption_epoch",
+            vault.key().as_ref(),
+            vault.current_epoch.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub redemption_epoch: Account<'info, RedemptionEpoch>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultCloseRedemptionEpoch<'info> {
+    pub fn close_epoch(&mut self, nav: u64, bump: u8) -> Result<()> {
+        let epoch = self.vault.close_redemption_epoch(nav)?;
+        self.redemption_epoch.initialize(self.vault.key(), epoch, nav, bump)?;
+
+        emit!(RedemptionEpochCloseEvent {
+            vault: self.vault.key(),
+            epoch,
+            nav,
+        });
+
+        Ok(())
+    }
+}
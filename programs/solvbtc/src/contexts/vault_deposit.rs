@@ -1,4 +1,4 @@
-use crate::{errors::SolvError, events::DepositEvent, helpers::{mint_to_checked_1_of_n_multisig, MintToChecked1ofNMultisig}, state::Vault};
+use crate::{constants::PAUSE_DEPOSIT, errors::SolvError, events::DepositEvent, helpers::mint_from_vault, state::{UserDeposit, Vault}};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
@@ -30,6 +30,12 @@ pub struct VaultDeposit<'info> {
         associated_token::mint = mint_token
     )]
     pub treasurer_token_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = vault.fee_receiver,
+        associated_token::mint = mint_target
+    )]
+    pub fee_receiver_target_ta: Box<InterfaceAccount<'info, TokenAccount>>,
     /// The multisig account that serves as mint authority
     /// CHECK: This is validated as a multisig account by the token program
     pub multisig: AccountInfo<'info>,
@@ -41,10 +47,21 @@ pub struct VaultDeposit<'info> {
         seeds = [b"vault", mint_target.key().as_ref()],
         bump = vault.bump,
         constraint = vault.is_whitelisted(&mint_token.key()),
+        constraint = !vault.is_paused(PAUSE_DEPOSIT) @ SolvError::VaultPaused,
     )]
     pub vault: Account<'info, Vault>,
+    /// Shares minted to the user, only required while the vault has a per-user cap
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserDeposit::DISCRIMINATOR.len() + UserDeposit::INIT_SPACE,
+        seeds = [b"user_deposit", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_deposit: Option<Box<Account<'info, UserDeposit>>>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 impl<'info> VaultDeposit<'info> {
@@ -61,30 +78,36 @@ impl<'info> VaultDeposit<'info> {
         transfer_checked(ctx, amount, self.mint_token.decimals)
     }
 
-    pub fn mint_target_tokens(&mut self, amount: u64, min_amount_out: u64) -> Result<()> {
-        let (mint_amount, fee_amount) = Vault::calculate_fee(self.vault.shares_from_deposit(amount)?, self.vault.deposit_fee(&self.mint_token.key())?)?;
+    pub fn mint_target_tokens(&mut self, amount: u64, min_amount_out: u64, user_deposit_bump: Option<u8>) -> Result<()> {
+        self.vault.check_nav_fresh()?;
+
+        let shares = self.vault.shares_from_deposit(&self.mint_token.key(), amount)?;
+        let (mint_amount, fee_amount) = Vault::calculate_fee(shares, self.vault.deposit_fee(&self.mint_token.key())?)?;
 
         // Slippage protection
         require_gte!(mint_amount, min_amount_out, SolvError::SlippageExceeded);
 
-        // For a 1/2 multisig, we only need 1 signature (the PDA)
-        let accounts = MintToChecked1ofNMultisig {
-            mint: self.mint_target.to_account_info(),
-            to: self.user_target_ta.to_account_info(),
-            multisig: self.multisig.to_account_info(),
-            signer: self.vault.to_account_info(),
-        };
-        
-        // Create PDA signer seeds for the multisig operation
-        let signer_seeds: [&[&[u8]];1] = [&[b"vault".as_ref(), self.mint_target.to_account_info().key.as_ref(), &[self.vault.bump]]];
+        // Deposit caps
+        self.vault.check_total_shares(self.mint_target.supply, shares)?;
+        self.vault.record_deposit(&self.mint_token.key(), amount)?;
+        if let Some(user_deposit) = self.user_deposit.as_mut() {
+            user_deposit.deposit(
+                self.vault.key(),
+                self.user.key(),
+                mint_amount,
+                self.vault.max_user_shares,
+                user_deposit_bump.ok_or(SolvError::UserDepositRequired)?,
+            )?;
+        } else {
+            require_eq!(self.vault.max_user_shares, 0, SolvError::UserDepositRequired);
+        }
 
-        let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(), 
-            accounts,
-            &signer_seeds
-        );
+        self.mint_shares(self.user_target_ta.to_account_info(), mint_amount)?;
 
-        mint_to_checked_1_of_n_multisig(ctx, mint_amount, self.mint_target.decimals)?;
+        // Deposit fee is collected as target shares minted to the fee receiver
+        if fee_amount > 0 {
+            self.mint_shares(self.fee_receiver_target_ta.to_account_info(), fee_amount)?;
+        }
 
         emit!(DepositEvent {
             user: self.user.key(),
@@ -98,4 +121,15 @@ impl<'info> VaultDeposit<'info> {
 
         Ok(())
     }
+
+    fn mint_shares(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        mint_from_vault(
+            self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_target,
+            self.multisig.to_account_info(),
+            to,
+            amount,
+        )
+    }
 }
@@ -0,0 +1,88 @@
+use crate::{
+    constants::PAUSE_WITHDRAW_REQUEST,
+    errors::SolvError,
+    events::WithdrawExpireEvent,
+    helpers::{close_request_account, load_request_account, mint_from_vault},
+    state::Vault,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+#[instruction(sequence: u64)]
+pub struct VaultExpireWithdrawRequest<'info> {
+    /// Anyone may expire a withdraw request
+    pub keeper: Signer<'info>,
+    /// CHECK: Validated against the withdraw request, receives the rent refund
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [
+            b"withdraw_request", 
+            vault.key().as_ref(),
+            sequence.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    /// CHECK: We manually deserialize this in the function body
+    pub withdraw_request: AccountInfo<'info>,
+    #[account(
+        mut,
+        token::authority = vault.expired_shares_recipient(user.key()),
+        token::mint = mint_target
+    )]
+    pub recipient_target_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The multisig account that serves as mint authority
+    /// CHECK: This is validated as a multisig account by the token program
+    pub multisig: AccountInfo<'info>,
+    pub mint_withdraw: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub mint_target: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"vault", mint_target.key().as_ref()],
+        bump = vault.bump,
+        constraint = !vault.is_paused(PAUSE_WITHDRAW_REQUEST) @ SolvError::VaultPaused,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> VaultExpireWithdrawRequest<'info> {
+    pub fn expire_request(&mut self) -> Result<()> {
+        // Get withdraw request
+        let withdraw_request = load_request_account(&self.withdraw_request)?;
+        withdraw_request.check_owner(&self.user.key(), &self.mint_withdraw.key())?;
+
+        require!(withdraw_request.is_expired()?, SolvError::WithdrawRequestNotExpired);
+
+        // Only shares of the amount not yet filled are minted
+        let shares = withdraw_request.unfilled_shares()?;
+        mint_from_vault(
+            self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_target,
+            self.multisig.to_account_info(),
+            self.recipient_target_ta.to_account_info(),
+            shares,
+        )?;
+
+        emit!(WithdrawExpireEvent {
+            user: withdraw_request.user,
+            vault: self.vault.key(),
+            withdraw_token: withdraw_request.withdraw_token,
+            token: withdraw_request.token,
+            shares,
+            sequence: withdraw_request.sequence,
+            recipient: self.recipient_target_ta.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Resize the withdraw_request account to zero bytes and refund Rent to user
+    pub fn close_request_account(&mut self) -> Result<()> {
+        close_request_account(&self.withdraw_request, &self.user.to_account_info())
+    }
+}
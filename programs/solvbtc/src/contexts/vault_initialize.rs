@@ -32,17 +32,20 @@ impl<'info> VaultInitialize<'info> {
         oracle_manager: Pubkey, 
         nav: u64,
         withdraw_fee: u16, 
+        chain_id: u64,
         bump: u8
     ) -> Result<()> {
         self.vault.initialize(
             admin,
             self.mint.key(),
+            self.mint.decimals,
             fee_receiver,
             treasurer,
             verifier,
             oracle_manager,
             nav,
             withdraw_fee,
+            chain_id,
             bump,
         )
     }
@@ -0,0 +1,163 @@
+use crate::{
+    constants::{PAUSE_WITHDRAW, PAUSE_WITHDRAW_REQUEST},
+    errors::SolvError,
+    events::InstantRedeemEvent,
+    helpers::{create_request_account, save_request_account, transfer_from_vault},
+    state::{Vault, WithdrawRequest},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{burn_checked, BurnChecked, Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+pub struct VaultInstantRedeem<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = mint_target
+    )]
+    pub user_target_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = mint_withdraw
+    )]
+    pub user_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub mint_target: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_withdraw: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"vault", mint_target.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.is_whitelisted(&mint_withdraw.key()),
+        constraint = !vault.is_paused(PAUSE_WITHDRAW) @ SolvError::VaultPaused,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// Withdraw request opened for the shares when the redemption cannot be paid instantly, only
+    /// required to allow that fallback
+    /// CHECK: Validated against the next sequence number when the fallback runs
+    #[account(mut)]
+    pub withdraw_request: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        associated_token::authority = vault,
+        associated_token::mint = mint_withdraw
+    )]
+    pub vault_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = vault.fee_receiver,
+        associated_token::mint = mint_withdraw
+    )]
+    pub fee_receiver_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultInstantRedeem<'info> {
+    /// Burn shares and pay out at the current NAV from the liquidity held by the vault above the
+    /// reserve, or open a withdraw request for the signed flow if that liquidity is short or the
+    /// cap of the current window is reached
+    pub fn instant_redeem(&mut self, shares: u64, min_amount_out: u64) -> Result<()> {
+        // Ensure no zero values are redeemed
+        if shares.eq(&0) {
+            return Err(SolvError::MathOverflow)?;
+        }
+        require_gt!(self.vault.instant_redeem_cap, 0, SolvError::InstantRedeemDisabled);
+
+        self.vault.check_nav_fresh()?;
+
+        let withdraw_amount = self.vault.withdrawal_from_shares(&self.mint_withdraw.key(), shares)?;
+        let liquidity = self.vault.instant_redeem_liquidity(&self.mint_withdraw.key(), self.vault_withdraw_ta.amount)?;
+
+        let instant = liquidity >= withdraw_amount && self.vault.record_instant_redeem(shares)?;
+
+        // A fallback withdraw request is charged the withdraw fee when it is paid out
+        let fee_ratio = if instant { self.vault.instant_redeem_fee } else { self.vault.withdraw_fee };
+        let (amount, fee) = Vault::calculate_fee(withdraw_amount, fee_ratio)?;
+
+        // Slippage protection, applied to the current value of a fallback withdraw request as well
+        require_gte!(amount, min_amount_out, SolvError::SlippageExceeded);
+
+        let accounts = BurnChecked {
+            mint: self.mint_target.to_account_info(),
+            from: self.user_target_ta.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(self.token_program.to_account_info(), accounts);
+
+        burn_checked(ctx, shares, self.mint_target.decimals)?;
+
+        if !instant {
+            return self.open_request_account(shares);
+        }
+
+        // Instant redemption fee
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_withdraw,
+            self.vault_withdraw_ta.to_account_info(),
+            self.fee_receiver_ta.to_account_info(),
+            fee,
+        )?;
+
+        // Withdraw amount
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_withdraw,
+            self.vault_withdraw_ta.to_account_info(),
+            self.user_withdraw_ta.to_account_info(),
+            amount,
+        )?;
+
+        emit!(InstantRedeemEvent {
+            user: self.user.key(),
+            withdraw_token: self.mint_withdraw.key(),
+            withdraw_amount: amount,
+            token: self.mint_target.key(),
+            shares,
+            nav: self.vault.nav,
+            withdraw_fee: fee,
+        });
+
+        Ok(())
+    }
+
+    /// Open a withdraw request for the burned shares, as vault_withdraw_request would
+    fn open_request_account(&mut self, shares: u64) -> Result<()> {
+        require!(!self.vault.is_paused(PAUSE_WITHDRAW_REQUEST), SolvError::VaultPaused);
+        let withdraw_request_info = self.withdraw_request.as_ref().ok_or(SolvError::WithdrawRequestRequired)?;
+
+        let vault = self.vault.key();
+        let sequence = self.vault.withdraw_request_count.to_le_bytes();
+        let (address, bump) = Pubkey::find_program_address(&[b"withdraw_request", vault.as_ref(), sequence.as_ref()], &crate::ID);
+        require_keys_eq!(withdraw_request_info.key(), address, SolvError::InvalidAddress);
+        create_request_account(
+            &self.user.to_account_info(),
+            withdraw_request_info,
+            &self.system_program.to_account_info(),
+            &[b"withdraw_request", vault.as_ref(), sequence.as_ref(), &[bump]],
+        )?;
+
+        let mut withdraw_request = WithdrawRequest::try_deserialize_unchecked(&mut &withdraw_request_info.try_borrow_data()?[..])?;
+        self.vault.open_withdraw_request(
+            &mut withdraw_request,
+            vault,
+            self.user.key(),
+            self.user_withdraw_ta.key(),
+            self.mint_withdraw.key(),
+            self.mint_target.key(),
+            shares,
+        )?;
+        save_request_account(withdraw_request_info, &withdraw_request)
+    }
+}
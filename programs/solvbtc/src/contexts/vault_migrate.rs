@@ -0,0 +1,46 @@
+use crate::{helpers::migrate_account, state::Vault};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct VaultMigrate<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Separate authority from payer to support multisig and PDA signers
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// The vault in its previous layout, checked and deserialized once it is resized
+    /// CHECK: Owner, discriminator and admin are validated in migrate
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultMigrate<'info> {
+    pub fn migrate(&mut self, chain_id: u64, currencies: &'info [AccountInfo<'info>]) -> Result<()> {
+        migrate_account(
+            &self.vault.to_account_info(),
+            &self.payer.to_account_info(),
+            &self.system_program.to_account_info(),
+            Vault::DISCRIMINATOR,
+            Vault::DISCRIMINATOR.len() + Vault::INIT_SPACE,
+        )?;
+
+        let mut vault = Vault::try_deserialize(&mut &self.vault.try_borrow_data()?[..])?;
+        require_keys_eq!(vault.admin, self.admin.key(), ErrorCode::ConstraintHasOne);
+
+        // Currency mints are passed in the order of the deposit currencies
+        let currencies = currencies
+            .iter()
+            .map(|currency| Ok((currency.key(), InterfaceAccount::<Mint>::try_from(currency)?.decimals)))
+            .collect::<Result<Vec<_>>>()?;
+        vault.migrate(self.mint.decimals, chain_id, &currencies)?;
+
+        vault.try_serialize(&mut &mut self.vault.try_borrow_mut_data()?[..])
+    }
+}
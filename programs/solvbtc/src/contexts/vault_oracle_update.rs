@@ -13,6 +13,11 @@ impl<'info> VaultOracleUpdate<'info> {
         require_keys_eq!(self.vault.oracle_manager, self.oracle_manager.key(), SolvError::InvalidAddress);
         self.vault.set_nav(nav)
     }
+
+    pub fn set_currency_rate(&mut self, currency: Pubkey, rate: u64) -> Result<()> {
+        require_keys_eq!(self.vault.oracle_manager, self.oracle_manager.key(), SolvError::InvalidAddress);
+        self.vault.set_currency_rate(currency, rate)
+    }
     
     pub fn set_manager(&mut self, manager: Pubkey) -> Result<()> {
         require_keys_eq!(self.vault.admin, self.oracle_manager.key(), SolvError::InvalidAddress);
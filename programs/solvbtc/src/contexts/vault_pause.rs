@@ -0,0 +1,29 @@
+use crate::{errors::SolvError, events::PausedStateChanged, state::Vault};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct VaultPause<'info> {
+    /// Either the vault admin or guardian
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = authority.key().eq(&vault.admin) || authority.key().eq(&vault.guardian) @ SolvError::InvalidAddress
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+impl<'info> VaultPause<'info> {
+    pub fn set_paused(&mut self, paused: u8) -> Result<()> {
+        let previous = self.vault.paused;
+        self.vault.set_paused(&self.authority.key(), paused)?;
+
+        emit!(PausedStateChanged {
+            account: self.vault.key(),
+            authority: self.authority.key(),
+            previous,
+            paused,
+        });
+
+        Ok(())
+    }
+}
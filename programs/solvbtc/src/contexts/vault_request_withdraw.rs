@@ -1,5 +1,5 @@
 use crate::{
-    errors::SolvError, state::{Vault, WithdrawRequest}
+    constants::PAUSE_WITHDRAW_REQUEST, errors::SolvError, helpers::check_withdraw_destination, state::{Vault, WithdrawRequest}
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
@@ -8,7 +8,6 @@ use anchor_spl::{
 };
 
 #[derive(Accounts)]
-#[instruction(hash: [u8;32])]
 pub struct VaultRequestWithdraw<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -18,9 +17,8 @@ pub struct VaultRequestWithdraw<'info> {
         token::mint = mint_target
     )]
     pub user_target_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Destination of the withdrawal, any token account of the withdraw mint
     #[account(
-        mut,
-        token::authority = user,
         token::mint = mint_withdraw
     )]
     pub user_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
@@ -34,9 +32,7 @@ pub struct VaultRequestWithdraw<'info> {
         seeds = [
             b"withdraw_request", 
             vault.key().as_ref(),
-            mint_withdraw.key().as_ref(),
-            user.key().as_ref(),
-            hash.as_ref()
+            vault.withdraw_request_count.to_le_bytes().as_ref()
         ],
         bump,
     )]
@@ -45,9 +41,16 @@ pub struct VaultRequestWithdraw<'info> {
         mut,
         seeds = [b"vault", mint_target.key().as_ref()],
         bump = vault.bump,
-        constraint = vault.is_whitelisted(&mint_withdraw.key())
+        constraint = vault.is_whitelisted(&mint_withdraw.key()),
+        constraint = !vault.is_paused(PAUSE_WITHDRAW_REQUEST) @ SolvError::VaultPaused,
     )]
     pub vault: Account<'info, Vault>,
+    /// CHECK: Address book of the user, validated by seeds and only read if it has been created
+    #[account(
+        seeds = [b"address_book", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub address_book: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -71,22 +74,18 @@ impl<'info> VaultRequestWithdraw<'info> {
         burn_checked(ctx, amount, self.mint_target.decimals)
     }
 
-    pub fn open_request_account(
-        &mut self,
-        request_hash: [u8; 32],
-        shares: u64,
-    ) -> Result<()> {
-        let withdraw_amount = self.vault.withdrawal_from_shares(shares)?;
+    pub fn open_request_account(&mut self, shares: u64) -> Result<()> {
+        check_withdraw_destination(&self.address_book, &self.user.key(), &self.user_withdraw_ta, self.vault.address_book_delay)?;
 
-        self.withdraw_request.initialize(
+        let vault = self.vault.key();
+        self.vault.open_withdraw_request(
+            &mut self.withdraw_request,
+            vault,
             self.user.key(),
             self.user_withdraw_ta.key(),
             self.mint_withdraw.key(),
-            withdraw_amount,
             self.mint_target.key(),
             shares,
-            request_hash,
-            self.vault.nav,
         )
     }
 }
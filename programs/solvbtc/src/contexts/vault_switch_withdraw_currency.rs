@@ -0,0 +1,123 @@
+use crate::{
+    constants::PAUSE_WITHDRAW_REQUEST,
+    errors::SolvError,
+    helpers::{check_withdraw_destination, close_request_account, load_request_account, load_verifier_set},
+    state::{Vault, VerifierSignature, WithdrawRequest},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+#[instruction(sequence: u64)]
+pub struct VaultSwitchWithdrawCurrency<'info> {
+    /// The user or anyone holding the verifier approval, pays for the new withdraw request and
+    /// is refunded the rent of the replaced one
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Validated against the withdraw request
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [
+            b"withdraw_request",
+            vault.key().as_ref(),
+            sequence.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    /// CHECK: We manually deserialize this in the function body
+    pub withdraw_request: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = WithdrawRequest::DISCRIMINATOR.len() + WithdrawRequest::INIT_SPACE,
+        seeds = [
+            b"withdraw_request",
+            vault.key().as_ref(),
+            vault.withdraw_request_count.to_le_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub new_withdraw_request: Account<'info, WithdrawRequest>,
+    /// Destination of the withdrawal, any token account of the withdraw mint
+    #[account(
+        token::mint = mint_withdraw
+    )]
+    pub user_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Currency the request is paid out in after the switch
+    pub mint_withdraw: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.mint.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.is_whitelisted(&mint_withdraw.key()),
+        constraint = !vault.is_paused(PAUSE_WITHDRAW_REQUEST) @ SolvError::VaultPaused,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Address book of the user, validated by seeds and only read if it has been created
+    #[account(
+        seeds = [b"address_book", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub address_book: UncheckedAccount<'info>,
+    /// CHECK: Verifier set of the vault, validated by seeds and only read if it has been created
+    #[account(
+        seeds = [b"verifier_set", vault.key().as_ref()],
+        bump
+    )]
+    pub verifier_set: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, required by vaults with an Ed25519 verifier
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultSwitchWithdrawCurrency<'info> {
+    /// Move the unfilled amount of a withdraw request approved by the verifier to a new
+    /// request paid out in another currency
+    pub fn switch_currency(&mut self, signatures: Vec<VerifierSignature>) -> Result<()> {
+        // Get withdraw request
+        let withdraw_request = load_request_account(&self.withdraw_request)?;
+        require_keys_eq!(withdraw_request.user, self.user.key(), SolvError::InvalidAddress);
+        require!(!withdraw_request.is_expired()?, SolvError::WithdrawRequestExpired);
+        check_withdraw_destination(&self.address_book, &self.user.key(), &self.user_withdraw_ta, self.vault.address_book_delay)?;
+
+        // Verify signatures
+        let vault = self.vault.key();
+        let chain_id = self.vault.chain_id;
+        self.vault.rotate_verifier(vault)?;
+        let verifier_set = load_verifier_set(&self.verifier_set)?;
+        self.vault.verify_withdraw_signatures(verifier_set.as_ref(), self.instructions.as_deref(), &signatures, |version| {
+            withdraw_request.switch_hash(&vault, chain_id, version, &self.mint_withdraw.key(), &self.user_withdraw_ta.key())
+        })?;
+
+        // Requests awaiting their redemption epoch are priced when the epoch is settled
+        let withdraw_amount = match withdraw_request.epoch {
+            Some(_) => 0,
+            None => self.vault.withdrawal_from_shares_at(
+                &self.mint_withdraw.key(),
+                withdraw_request.unfilled_shares()?,
+                withdraw_request.nav,
+            )?,
+        };
+
+        let sequence = self.vault.next_withdraw_sequence()?;
+        let new_withdraw_request = withdraw_request.switch_currency(
+            self.vault.key(),
+            self.user_withdraw_ta.key(),
+            self.mint_withdraw.key(),
+            withdraw_amount,
+            sequence,
+        )?;
+        self.new_withdraw_request.set_inner(new_withdraw_request);
+
+        Ok(())
+    }
+
+    /// Resize the previous withdraw_request account to zero bytes and refund Rent to the payer of
+    /// the new request, so a third party funding the switch does not pay the user's rent
+    pub fn close_request_account(&mut self) -> Result<()> {
+        close_request_account(&self.withdraw_request, &self.payer.to_account_info())
+    }
+}
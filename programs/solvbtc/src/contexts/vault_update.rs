@@ -1,4 +1,7 @@
-use crate::state::Vault;
+use crate::{
+    events::{MaxNavChangeChanged, WithdrawNavToleranceChanged},
+    state::{ExpiryPolicy, SettlementPolicy, Vault, VerifierType},
+};
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::Mint;
 
@@ -28,6 +31,80 @@ impl<'info> VaultUpdate<'info> {
         self.vault.set_deposit_fee(currency, deposit_fee)
     }
 
+    pub fn set_max_total_shares(&mut self, max_total_shares: u64) -> Result<()> {
+        self.vault.set_max_total_shares(max_total_shares)
+    }
+
+    pub fn set_max_user_shares(&mut self, max_user_shares: u64) -> Result<()> {
+        self.vault.set_max_user_shares(max_user_shares)
+    }
+
+    pub fn set_deposit_cap(&mut self, currency: Pubkey, deposit_cap: u64) -> Result<()> {
+        self.vault.set_deposit_cap(currency, deposit_cap)
+    }
+
+    pub fn set_max_nav_age(&mut self, max_nav_age: u32) -> Result<()> {
+        self.vault.set_max_nav_age(max_nav_age)
+    }
+
+    pub fn set_cancel_delay(&mut self, cancel_delay: u32) -> Result<()> {
+        self.vault.set_cancel_delay(cancel_delay)
+    }
+
+    pub fn set_withdraw_expiry(&mut self, withdraw_request_ttl: u32, expiry_policy: ExpiryPolicy) -> Result<()> {
+        self.vault.set_withdraw_expiry(withdraw_request_ttl, expiry_policy)
+    }
+
+    pub fn set_withdraw_nav_tolerance(&mut self, withdraw_nav_tolerance: u16) -> Result<()> {
+        let previous = self.vault.withdraw_nav_tolerance;
+        self.vault.set_withdraw_nav_tolerance(withdraw_nav_tolerance)?;
+
+        emit!(WithdrawNavToleranceChanged {
+            vault: self.vault.key(),
+            previous,
+            withdraw_nav_tolerance,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_max_nav_change(&mut self, max_nav_change: u16) -> Result<()> {
+        let previous = self.vault.max_nav_change;
+        self.vault.set_max_nav_change(max_nav_change)?;
+
+        emit!(MaxNavChangeChanged {
+            vault: self.vault.key(),
+            previous,
+            max_nav_change,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_settlement_policy(&mut self, settlement_policy: SettlementPolicy) -> Result<()> {
+        self.vault.set_settlement_policy(settlement_policy)
+    }
+
+    pub fn set_epoch_mode(&mut self, epoch_mode: bool) -> Result<()> {
+        self.vault.set_epoch_mode(epoch_mode)
+    }
+
+    pub fn set_instant_redeem(&mut self, instant_redeem_fee: u16, instant_redeem_cap: u64, instant_redeem_window: u32) -> Result<()> {
+        self.vault.set_instant_redeem(instant_redeem_fee, instant_redeem_cap, instant_redeem_window)
+    }
+
+    pub fn set_instant_redeem_reserve(&mut self, currency: Pubkey, instant_redeem_reserve: u64) -> Result<()> {
+        self.vault.set_instant_redeem_reserve(currency, instant_redeem_reserve)
+    }
+
+    pub fn set_chain_id(&mut self, chain_id: u64) -> Result<()> {
+        self.vault.set_chain_id(chain_id)
+    }
+
+    pub fn set_address_book_delay(&mut self, address_book_delay: u32) -> Result<()> {
+        self.vault.set_address_book_delay(address_book_delay)
+    }
+
     pub fn set_withdraw_fee(&mut self, withdraw_fee: u16) -> Result<()> {
         self.vault.set_withdraw_fee(withdraw_fee)
     }
@@ -40,12 +117,37 @@ impl<'info> VaultUpdate<'info> {
         self.vault.set_verifier(verifier)
     }
 
-    pub fn set_treasurer(&mut self, treasurer: Pubkey) -> Result<()> {
-        self.vault.set_treasurer(treasurer)
+    pub fn set_recover_verifier(&mut self, verifier: [u8; 64]) -> Result<()> {
+        self.vault.set_recover_verifier(verifier)
+    }
+
+    pub fn set_ed25519_verifier(&mut self, verifier: Pubkey) -> Result<()> {
+        self.vault.set_ed25519_verifier(verifier)
     }
 
-    pub fn add_currency(&mut self, currency: Pubkey, deposit_fee: u16) -> Result<()> {
-        self.vault.add_currency(currency, deposit_fee)
+    pub fn set_schnorr_verifier(&mut self, verifier: [u8; 32]) -> Result<()> {
+        self.vault.set_schnorr_verifier(verifier)
+    }
+
+    pub fn stage_verifier(&mut self, verifier: [u8; 64], verifier_type: VerifierType, activates_at: i64) -> Result<()> {
+        let vault = self.vault.key();
+        self.vault.stage_verifier(vault, verifier, verifier_type, activates_at)
+    }
+
+    pub fn set_verifier_overlap(&mut self, verifier_overlap: u32) -> Result<()> {
+        self.vault.set_verifier_overlap(verifier_overlap)
+    }
+
+    pub fn set_eip712_verifier(&mut self, address: [u8; 20]) -> Result<()> {
+        self.vault.set_eip712_verifier(address)
+    }
+
+    pub fn set_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        self.vault.set_guardian(guardian)
+    }
+
+    pub fn set_treasurer(&mut self, treasurer: Pubkey) -> Result<()> {
+        self.vault.set_treasurer(treasurer)
     }
 
     pub fn remove_currency(&mut self, currency: Pubkey) -> Result<()> {
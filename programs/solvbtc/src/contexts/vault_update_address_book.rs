@@ -0,0 +1,32 @@
+use crate::state::{AddressBook, Vault};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct VaultUpdateAddressBook<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        seeds = [b"vault", vault.mint.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = AddressBook::DISCRIMINATOR.len() + AddressBook::INIT_SPACE,
+        seeds = [b"address_book", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub address_book: Box<Account<'info, AddressBook>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultUpdateAddressBook<'info> {
+    pub fn add_entry(&mut self, token_account: Pubkey, bump: u8) -> Result<()> {
+        self.address_book.add_entry(self.vault.key(), self.user.key(), token_account, bump)
+    }
+
+    pub fn remove_entry(&mut self, token_account: Pubkey) -> Result<()> {
+        self.address_book.remove_entry(token_account)
+    }
+}
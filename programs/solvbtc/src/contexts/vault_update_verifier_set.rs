@@ -0,0 +1,61 @@
+use crate::{
+    errors::SolvError,
+    events::VerifierSetChanged,
+    state::{Vault, VerifierSet, VerifierType},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct VaultUpdateVerifierSet<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Separate authority from payer to support multisig and PDA signers
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        has_one = admin,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = VerifierSet::DISCRIMINATOR.len() + VerifierSet::INIT_SPACE,
+        seeds = [b"verifier_set", vault.key().as_ref()],
+        bump
+    )]
+    pub verifier_set: Box<Account<'info, VerifierSet>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> VaultUpdateVerifierSet<'info> {
+    pub fn add_verifier(&mut self, verifier: [u8; 64], verifier_type: VerifierType, bump: u8) -> Result<()> {
+        self.verifier_set.add_verifier(self.vault.key(), verifier, verifier_type, bump)?;
+        self.emit_changed();
+        Ok(())
+    }
+
+    pub fn remove_verifier(&mut self, verifier: [u8; 64]) -> Result<()> {
+        self.verifier_set.remove_verifier(verifier)?;
+        self.emit_changed();
+        Ok(())
+    }
+
+    pub fn set_threshold(&mut self, threshold: u8) -> Result<()> {
+        // Members must be added before a threshold is set
+        require_keys_eq!(self.verifier_set.vault, self.vault.key(), SolvError::VerifierNotFound);
+        self.verifier_set.set_threshold(threshold)?;
+        self.emit_changed();
+        Ok(())
+    }
+
+    fn emit_changed(&self) {
+        emit!(VerifierSetChanged {
+            vault: self.vault.key(),
+            threshold: self.verifier_set.threshold,
+            members: self.verifier_set.members(),
+        });
+    }
+}
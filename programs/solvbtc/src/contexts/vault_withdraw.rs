@@ -1,17 +1,15 @@
-use crate::events::WithdrawEvent;
-use crate::state::{Vault, WithdrawRequest};
+use crate::helpers::{load_request_account, load_verifier_set, pay_withdraw_request};
+use crate::state::{RedemptionEpoch, Vault, VerifierSignature, WithdrawRequest};
 use crate::errors::SolvError;
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
-use crate::{constants::{MAX_FEE}};
-
-use solana_secp256k1_ecdsa::Secp256k1EcdsaSignature;
+use crate::constants::PAUSE_WITHDRAW;
 
 #[derive(Accounts)]
-#[instruction(hash: [u8;32])]
+#[instruction(sequence: u64)]
 pub struct VaultWithdraw<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -21,17 +19,15 @@ pub struct VaultWithdraw<'info> {
         seeds = [
             b"withdraw_request", 
             vault.key().as_ref(),
-            mint_withdraw.key().as_ref(),
-            user.key().as_ref(),
-            hash.as_ref(),
+            sequence.to_le_bytes().as_ref(),
         ],
         bump
     )]
     /// CHECK: We manually deserialize this in the function body
     pub withdraw_request: AccountInfo<'info>,
+    /// Must match the token account recorded in the withdraw request
     #[account(
         mut,
-        token::authority = user,
         token::mint = mint_withdraw,
     )]
     pub user_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
@@ -40,9 +36,24 @@ pub struct VaultWithdraw<'info> {
         mut,
         seeds = [b"vault",vault.mint.key().as_ref()],
         bump = vault.bump,
-        constraint = vault.is_whitelisted(&mint_withdraw.key())
+        constraint = vault.is_whitelisted(&mint_withdraw.key()),
+        constraint = !vault.is_paused(PAUSE_WITHDRAW) @ SolvError::VaultPaused,
     )]
     pub vault: Account<'info, Vault>,
+    /// Redemption epoch pricing the withdraw request, required for requests made in epoch mode
+    #[account(
+        constraint = redemption_epoch.vault.eq(&vault.key()) @ SolvError::InvalidAddress
+    )]
+    pub redemption_epoch: Option<Account<'info, RedemptionEpoch>>,
+    /// CHECK: Verifier set of the vault, validated by seeds and only read if it has been created
+    #[account(
+        seeds = [b"verifier_set", vault.key().as_ref()],
+        bump
+    )]
+    pub verifier_set: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, required by vaults with an Ed25519 verifier
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
     #[account(
         mut,
         associated_token::authority = vault,
@@ -61,95 +72,62 @@ pub struct VaultWithdraw<'info> {
 }
 
 impl<'info> VaultWithdraw<'info> {
-    pub fn withdraw_tokens(&mut self, signature: [u8;64]) -> Result<()> {
+    /// Fill the remaining amount of a withdraw request approved in full by the verifier
+    pub fn withdraw_tokens(&mut self, signatures: Vec<VerifierSignature>) -> Result<()> {
+        let mut withdraw_request = self.load_request()?;
+
+        // Verify signatures
+        let vault = self.vault.key();
+        let chain_id = self.vault.chain_id;
+        self.vault.rotate_verifier(vault)?;
+        let verifier_set = load_verifier_set(&self.verifier_set)?;
+        self.vault.verify_withdraw_signatures(verifier_set.as_ref(), self.instructions.as_deref(), &signatures, |version| withdraw_request.hash(&vault, chain_id, version))?;
+
+        let amount = withdraw_request.remaining_amount()?;
+        self.fill_request(&mut withdraw_request, amount)
+    }
+
+    /// Fill part of a withdraw request with an amount approved by the verifier
+    pub fn withdraw_partial(&mut self, amount: u64, signatures: Vec<VerifierSignature>) -> Result<()> {
+        let mut withdraw_request = self.load_request()?;
+
+        // Verify signatures
+        let vault = self.vault.key();
+        let chain_id = self.vault.chain_id;
+        self.vault.rotate_verifier(vault)?;
+        let verifier_set = load_verifier_set(&self.verifier_set)?;
+        self.vault.verify_withdraw_signatures(verifier_set.as_ref(), self.instructions.as_deref(), &signatures, |version| withdraw_request.fill_hash(&vault, chain_id, version, amount))?;
+
+        self.fill_request(&mut withdraw_request, amount)
+    }
+
+    fn load_request(&self) -> Result<WithdrawRequest> {
         // Get withdraw request
-        let mut withdraw_request_data = &self.withdraw_request.data.borrow()[..];
-        let withdraw_request = WithdrawRequest::try_deserialize(&mut withdraw_request_data)?;
+        let mut withdraw_request = load_request_account(&self.withdraw_request)?;
+        withdraw_request.check_owner(&self.user.key(), &self.mint_withdraw.key())?;
+        self.vault.settle_withdraw_request(&mut withdraw_request, self.redemption_epoch.as_deref())?;
 
         // Verify withdraw account address;
         if self.user_withdraw_ta.key().ne(&withdraw_request.withdraw_token_account) {
             return Err(SolvError::InvalidAddress)?;
         }
 
-        // Verify signature
-        withdraw_request.verify_signature(Secp256k1EcdsaSignature(signature), self.vault.verifier)?;
-
-        // Check 1.01*nav >= nav of withdraw request
-        let nav_diff: u64 = u64::try_from(u128::from(self.vault.nav)
-            .checked_mul(100 as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(MAX_FEE.into())
-            .ok_or(ProgramError::ArithmeticOverflow)?)
-            .map_err(|_| ProgramError::ArithmeticOverflow)?;
-        let max_nav = self.vault.nav.checked_add(nav_diff).ok_or(ProgramError::ArithmeticOverflow)?;
-        require_gte!(max_nav, withdraw_request.nav, SolvError::NAVExceeded);
-
-        // Get withdraw amount and withdraw fee
-        let (amount, fee) = Vault::calculate_fee(withdraw_request.withdraw_amount, self.vault.withdraw_fee)?;
-        msg!("Withdraw amount: {}, Fee: {}", amount, fee);
-
-        // Signer seeds
-        let key = self.vault.mint.key();
-        let bump = [self.vault.bump];
-        let signer_seeds: [&[&[u8]]; 1] = [&[b"vault", key.as_ref(), bump.as_ref()]];
-        // Withdraw fee
-        let accounts = TransferChecked {
-            from: self.vault_withdraw_ta.to_account_info(),
-            to: self.fee_receiver_ta.to_account_info(),
-            mint: self.mint_withdraw.to_account_info(),
-            authority: self.vault.to_account_info(),
-        };
-
-        let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            accounts,
-            &signer_seeds,
-        );
-
-        transfer_checked(ctx, fee, self.mint_withdraw.decimals)?;
-
-        // Withdraw amount
-        let accounts = TransferChecked {
-            from: self.vault_withdraw_ta.to_account_info(),
-            to: self.user_withdraw_ta.to_account_info(),
-            mint: self.mint_withdraw.to_account_info(),
-            authority: self.vault.to_account_info(),
-        };
-
-        let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            accounts,
-            &signer_seeds,
-        );
-
-        transfer_checked(ctx, amount, self.mint_withdraw.decimals)?;
-
-        emit!(WithdrawEvent {
-            user: self.user.key(),
-            withdraw_amount:amount, 
-            withdraw_token: self.mint_withdraw.key(), 
-            request_hash: withdraw_request.request_hash, 
-            withdraw_fee: fee,
-        });
-
-        Ok(())
+        Ok(withdraw_request)
     }
 
-    /// Resize the withdraw_request account to zero bytes and refund Rent to user
-    pub fn close_request_account(&mut self) -> Result<()> {
-        let rent = Rent::get()?;
-        let min_balance_for_zero = rent.minimum_balance(0);
-        let current_lamports = self.withdraw_request.lamports();
-
-        // Resize to zero first
-        self.withdraw_request.resize(0)?;
-
-        // Calculate how much to refund (keeping minimum for 0-byte account)
-        if current_lamports > min_balance_for_zero {
-            let refund = current_lamports - min_balance_for_zero;
-            self.withdraw_request.sub_lamports(refund)?;
-            self.user.add_lamports(refund)?;
-        }
+    fn fill_request(&mut self, withdraw_request: &mut WithdrawRequest, amount: u64) -> Result<()> {
+        pay_withdraw_request(
+            &self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_withdraw,
+            &self.vault_withdraw_ta.to_account_info(),
+            Some(&self.fee_receiver_ta.to_account_info()),
+            &self.user.to_account_info(),
+            &self.user_withdraw_ta.to_account_info(),
+            &self.withdraw_request,
+            withdraw_request,
+            amount,
+        )?;
         Ok(())
     }
 }
@@ -0,0 +1,140 @@
+use crate::helpers::{load_request_account, load_verifier_set, pay_withdraw_request, transfer_from_vault};
+use crate::state::{RedemptionEpoch, Vault, VerifierSignature};
+use crate::errors::SolvError;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::constants::PAUSE_WITHDRAW;
+
+/// Number of remaining accounts per withdraw request: withdraw request, user withdraw token account, user
+pub const WITHDRAW_BATCH_ACCOUNTS: usize = 3;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WithdrawApproval {
+    pub sequence: u64,
+    pub signatures: Vec<VerifierSignature>,
+}
+
+#[derive(Accounts)]
+pub struct VaultWithdrawBatch<'info> {
+    /// Anyone may submit signed withdraw requests on behalf of their users
+    pub keeper: Signer<'info>,
+    pub mint_withdraw: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"vault",vault.mint.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.is_whitelisted(&mint_withdraw.key()),
+        constraint = !vault.is_paused(PAUSE_WITHDRAW) @ SolvError::VaultPaused,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// Redemption epoch pricing the withdraw requests, required for requests made in epoch mode.
+    /// Every request awaiting settlement in the batch must belong to this epoch.
+    #[account(
+        constraint = redemption_epoch.vault.eq(&vault.key()) @ SolvError::InvalidAddress
+    )]
+    pub redemption_epoch: Option<Account<'info, RedemptionEpoch>>,
+    /// CHECK: Verifier set of the vault, validated by seeds and only read if it has been created
+    #[account(
+        seeds = [b"verifier_set", vault.key().as_ref()],
+        bump
+    )]
+    pub verifier_set: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, required by vaults with an Ed25519 verifier
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        associated_token::authority = vault,
+        associated_token::mint = mint_withdraw
+    )]
+    pub vault_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = vault.fee_receiver,
+        associated_token::mint = mint_withdraw
+    )]
+    pub fee_receiver_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> VaultWithdrawBatch<'info> {
+    /// Fill the remaining amount of each withdraw request approved in full by the verifier,
+    /// with the withdraw request, user withdraw token account and user of each approval
+    /// passed in order as remaining accounts
+    pub fn withdraw_batch(&mut self, approvals: Vec<WithdrawApproval>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require_eq!(
+            remaining_accounts.len(),
+            approvals.len().checked_mul(WITHDRAW_BATCH_ACCOUNTS).ok_or(ProgramError::ArithmeticOverflow)?,
+            SolvError::InvalidBatchAccounts
+        );
+
+        let vault = self.vault.key();
+        let chain_id = self.vault.chain_id;
+        self.vault.rotate_verifier(vault)?;
+        let verifier_set = load_verifier_set(&self.verifier_set)?;
+        let mut total_fee: u64 = 0;
+
+        for (approval, accounts) in approvals.iter().zip(remaining_accounts.chunks_exact(WITHDRAW_BATCH_ACCOUNTS)) {
+            let [withdraw_request_info, user_withdraw_ta, user] = accounts else {
+                return Err(SolvError::InvalidBatchAccounts.into());
+            };
+
+            // Get withdraw request at the address of its sequence number
+            let (withdraw_request_address, _) = Pubkey::find_program_address(
+                &[b"withdraw_request", vault.as_ref(), approval.sequence.to_le_bytes().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(withdraw_request_info.key(), withdraw_request_address, SolvError::InvalidAddress);
+            require_keys_eq!(*withdraw_request_info.owner, crate::ID, SolvError::InvalidAddress);
+            let mut withdraw_request = load_request_account(withdraw_request_info)?;
+
+            // Verify the withdraw request belongs to this vault, user and withdraw currency
+            require_keys_eq!(withdraw_request.token, self.vault.mint, SolvError::InvalidAddress);
+            require_eq!(withdraw_request.sequence, approval.sequence, SolvError::InvalidSequence);
+            withdraw_request.check_owner(&user.key(), &self.mint_withdraw.key())?;
+            if let Some(epoch) = withdraw_request.epoch {
+                require!(
+                    self.redemption_epoch.as_ref().is_some_and(|redemption_epoch| redemption_epoch.epoch.eq(&epoch)),
+                    SolvError::MixedEpochBatch
+                );
+            }
+            self.vault.settle_withdraw_request(&mut withdraw_request, self.redemption_epoch.as_deref())?;
+
+            // Verify withdraw account address
+            require_keys_eq!(user_withdraw_ta.key(), withdraw_request.withdraw_token_account, SolvError::InvalidAddress);
+
+            // Verify signatures
+            self.vault.verify_withdraw_signatures(verifier_set.as_ref(), self.instructions.as_deref(), &approval.signatures, |version| withdraw_request.hash(&vault, chain_id, version))?;
+
+            // Fees are paid below in a single transfer
+            let amount = withdraw_request.remaining_amount()?;
+            let fee = pay_withdraw_request(
+                &self.token_program.to_account_info(),
+                &self.vault,
+                &self.mint_withdraw,
+                &self.vault_withdraw_ta.to_account_info(),
+                None,
+                user,
+                user_withdraw_ta,
+                withdraw_request_info,
+                &mut withdraw_request,
+                amount,
+            )?;
+            total_fee = total_fee.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+
+        // Withdraw fees of all requests in a single transfer
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_withdraw,
+            self.vault_withdraw_ta.to_account_info(),
+            self.fee_receiver_ta.to_account_info(),
+            total_fee,
+        )
+    }
+}
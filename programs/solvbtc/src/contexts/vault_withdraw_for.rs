@@ -0,0 +1,113 @@
+use crate::helpers::{load_request_account, load_verifier_set, pay_withdraw_request};
+use crate::state::{RedemptionEpoch, Vault, VerifierSignature};
+use crate::errors::SolvError;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::constants::PAUSE_WITHDRAW;
+
+#[derive(Accounts)]
+#[instruction(sequence: u64)]
+pub struct VaultWithdrawFor<'info> {
+    /// Anyone may submit a signed withdraw request on behalf of the user
+    pub keeper: Signer<'info>,
+    /// CHECK: Validated against the withdraw request, receives the rent refund
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [
+            b"withdraw_request", 
+            vault.key().as_ref(),
+            sequence.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    /// CHECK: We manually deserialize this in the function body
+    pub withdraw_request: AccountInfo<'info>,
+    /// Must match the token account recorded in the withdraw request
+    #[account(
+        mut,
+        token::mint = mint_withdraw,
+    )]
+    pub user_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint_withdraw: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"vault",vault.mint.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.is_whitelisted(&mint_withdraw.key()),
+        constraint = !vault.is_paused(PAUSE_WITHDRAW) @ SolvError::VaultPaused,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// Redemption epoch pricing the withdraw request, required for requests made in epoch mode
+    #[account(
+        constraint = redemption_epoch.vault.eq(&vault.key()) @ SolvError::InvalidAddress
+    )]
+    pub redemption_epoch: Option<Account<'info, RedemptionEpoch>>,
+    /// CHECK: Verifier set of the vault, validated by seeds and only read if it has been created
+    #[account(
+        seeds = [b"verifier_set", vault.key().as_ref()],
+        bump
+    )]
+    pub verifier_set: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, required by vaults with an Ed25519 verifier
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        associated_token::authority = vault,
+        associated_token::mint = mint_withdraw
+    )]
+    pub vault_withdraw_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = vault.fee_receiver,
+        associated_token::mint = mint_withdraw
+    )]
+    pub fee_receiver_ta: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> VaultWithdrawFor<'info> {
+    /// Fill the remaining amount of a withdraw request approved in full by the verifier,
+    /// paying out to the token account recorded in the request
+    pub fn withdraw_tokens(&mut self, signatures: Vec<VerifierSignature>) -> Result<()> {
+        // Get withdraw request
+        let mut withdraw_request = load_request_account(&self.withdraw_request)?;
+        withdraw_request.check_owner(&self.user.key(), &self.mint_withdraw.key())?;
+        self.vault.settle_withdraw_request(&mut withdraw_request, self.redemption_epoch.as_deref())?;
+
+        // Verify withdraw account address;
+        if self.user_withdraw_ta.key().ne(&withdraw_request.withdraw_token_account) {
+            return Err(SolvError::InvalidAddress)?;
+        }
+
+        // Verify signatures
+        let vault = self.vault.key();
+        let chain_id = self.vault.chain_id;
+        self.vault.rotate_verifier(vault)?;
+        let verifier_set = load_verifier_set(&self.verifier_set)?;
+        self.vault.verify_withdraw_signatures(verifier_set.as_ref(), self.instructions.as_deref(), &signatures, |version| withdraw_request.hash(&vault, chain_id, version))?;
+
+        // The remaining amount is filled in full, closing the request to the user who paid for it
+        let amount = withdraw_request.remaining_amount()?;
+        pay_withdraw_request(
+            &self.token_program.to_account_info(),
+            &self.vault,
+            &self.mint_withdraw,
+            &self.vault_withdraw_ta.to_account_info(),
+            Some(&self.fee_receiver_ta.to_account_info()),
+            &self.user.to_account_info(),
+            &self.user_withdraw_ta.to_account_info(),
+            &self.withdraw_request,
+            &mut withdraw_request,
+            amount,
+        )?;
+        Ok(())
+    }
+}
@@ -30,4 +30,75 @@ pub enum SolvError {
     MathOverflow,
     #[msg("SolvOracle: Invalid Max NAV Change - must be <=10,000")]
     InvalidMaxNavChange,
+    // Variants are only ever appended, Anchor error codes follow declaration order
+    #[msg("SolvVault: Vault share cap exceeded")]
+    VaultCapExceeded,
+    #[msg("SolvVault: Currency deposit cap exceeded")]
+    CurrencyCapExceeded,
+    #[msg("SolvVault: User share cap exceeded")]
+    UserCapExceeded,
+    #[msg("SolvVault: User deposit account required")]
+    UserDepositRequired,
+    #[msg("SolvVault: Invalid withdraw request sequence")]
+    InvalidSequence,
+    #[msg("SolvVault: Withdraw request still pending")]
+    WithdrawRequestPending,
+    #[msg("SolvVault: Redemption epoch not settled")]
+    EpochNotSettled,
+    #[msg("SolvVault: Instant redemption disabled")]
+    InstantRedeemDisabled,
+    #[msg("SolvVault: Invalid instant redemption window")]
+    InvalidInstantRedeemWindow,
+    #[msg("SolvVault: Withdraw destination not allowed")]
+    InvalidWithdrawDestination,
+    #[msg("SolvVault: Address book full")]
+    AddressBookFull,
+    #[msg("SolvVault: Address book entry already exists")]
+    AddressBookEntryExists,
+    #[msg("SolvVault: Address book entry not found")]
+    AddressBookEntryNotFound,
+    #[msg("SolvVault: Verifier set full")]
+    VerifierSetFull,
+    #[msg("SolvVault: Verifier already exists")]
+    VerifierExists,
+    #[msg("SolvVault: Verifier not found")]
+    VerifierNotFound,
+    #[msg("SolvVault: Invalid verifier threshold")]
+    InvalidVerifierThreshold,
+    #[msg("SolvVault: Not enough verifier signatures")]
+    VerifierThresholdNotMet,
+    #[msg("SolvVault: Duplicate verifier signature")]
+    DuplicateVerifierSignature,
+    #[msg("SolvVault: Verifier activation must not be in the past")]
+    InvalidVerifierActivation,
+    #[msg("SolvVault: Invalid chain ID")]
+    InvalidChainId,
+    #[msg("SolvVault: Invalid verifier key format")]
+    InvalidVerifierKey,
+    #[msg("SolvVault: Invalid batch accounts")]
+    InvalidBatchAccounts,
+    #[msg("SolvVault: Batch requests must share the redemption epoch")]
+    MixedEpochBatch,
+    #[msg("SolvVault: Invalid fill amount")]
+    InvalidFillAmount,
+    #[msg("SolvVault: Withdraw request cannot be cancelled yet")]
+    CancelDelayNotElapsed,
+    #[msg("SolvVault: Withdraw request expired")]
+    WithdrawRequestExpired,
+    #[msg("SolvVault: Withdraw request not expired")]
+    WithdrawRequestNotExpired,
+    #[msg("SolvVault: Operation paused")]
+    VaultPaused,
+    #[msg("SolvVault: Guardian can only pause")]
+    GuardianCannotUnpause,
+    #[msg("SolvVault: Account already migrated")]
+    AlreadyMigrated,
+    #[msg("SolvMinterManager: Minting paused")]
+    MinterPaused,
+    #[msg("SolvOracle: NAV is stale")]
+    StaleNAV,
+    #[msg("SolvOracle: Invalid currency rate")]
+    InvalidCurrencyRate,
+    #[msg("SolvVault: Withdraw request account required")]
+    WithdrawRequestRequired,
 }
@@ -1,22 +1,99 @@
 use anchor_lang::prelude::*;
 
+use crate::state::VerifierType;
+
 #[event]
 pub struct WithdrawRequestEvent {
     pub user: Pubkey,
+    pub vault: Pubkey,
     pub withdraw_token: Pubkey,
     pub withdraw_amount: u64,
     pub token: Pubkey,
     pub shares: u64,
-    pub request_hash: [u8; 32],
+    pub sequence: u64,
     pub nav: u64,
+    pub epoch: Option<u64>,
 }
 
 #[event]
 pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub withdraw_token: Pubkey,
+    pub withdraw_amount: u64,
+    pub sequence: u64,
+    pub withdraw_fee: u64,
+    /// Amount withheld because the NAV fell below the request NAV
+    pub shortfall: u64,
+    pub filled_amount: u64,
+    pub remaining_amount: u64,
+}
+
+#[event]
+pub struct WithdrawCancelEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub withdraw_token: Pubkey,
+    pub token: Pubkey,
+    pub shares: u64,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct WithdrawExpireEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub withdraw_token: Pubkey,
+    pub token: Pubkey,
+    pub shares: u64,
+    pub sequence: u64,
+    pub recipient: Pubkey,
+}
+
+#[event]
+pub struct WithdrawSettleEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub withdraw_token: Pubkey,
+    pub withdraw_amount: u64,
+    pub sequence: u64,
+    pub epoch: u64,
+    pub nav: u64,
+}
+
+#[event]
+pub struct WithdrawSwitchEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub previous_sequence: u64,
+    pub previous_withdraw_token: Pubkey,
+    pub sequence: u64,
+    pub withdraw_token: Pubkey,
+    pub withdraw_amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct RedemptionEpochCloseEvent {
+    pub vault: Pubkey,
+    pub epoch: u64,
+    pub nav: u64,
+}
+
+#[event]
+pub struct WithdrawQueueAdvanceEvent {
+    pub vault: Pubkey,
+    pub head: u64,
+}
+
+#[event]
+pub struct InstantRedeemEvent {
     pub user: Pubkey,
     pub withdraw_token: Pubkey,
     pub withdraw_amount: u64,
-    pub request_hash: [u8; 32],
+    pub token: Pubkey,
+    pub shares: u64,
+    pub nav: u64,
     pub withdraw_fee: u64,
 }
 
@@ -29,4 +106,56 @@ pub struct DepositEvent {
     pub deposit_amount: u64,
     pub mint_amount: u64,
     pub fee_amount: u64,
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct WithdrawNavToleranceChanged {
+    pub vault: Pubkey,
+    pub previous: u16,
+    pub withdraw_nav_tolerance: u16,
+}
+
+#[event]
+pub struct MaxNavChangeChanged {
+    pub vault: Pubkey,
+    pub previous: u16,
+    pub max_nav_change: u16,
+}
+
+#[event]
+pub struct VerifierSetChanged {
+    pub vault: Pubkey,
+    pub threshold: u8,
+    pub members: u8,
+}
+
+#[event]
+pub struct VerifierStaged {
+    pub vault: Pubkey,
+    pub verifier: [u8; 64],
+    pub verifier_type: VerifierType,
+    pub activates_at: i64,
+}
+
+#[event]
+pub struct VerifierActivated {
+    pub vault: Pubkey,
+    pub verifier: [u8; 64],
+    pub verifier_type: VerifierType,
+    pub previous_verifier_expiry: i64,
+}
+
+#[event]
+pub struct VerifierRetired {
+    pub vault: Pubkey,
+    pub verifier: [u8; 64],
+    pub verifier_type: VerifierType,
+}
+
+#[event]
+pub struct PausedStateChanged {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub previous: u8,
+    pub paused: u8,
+}
@@ -1,5 +1,17 @@
-use anchor_lang::prelude::*;
-use anchor_spl::token::spl_token;
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer},
+};
+use anchor_spl::{
+    token::spl_token,
+    token_interface::{transfer_checked, Mint, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    errors::SolvError,
+    events::WithdrawEvent,
+    state::{AddressBook, Vault, VerifierSet, WithdrawRequest},
+};
 
 #[derive(Accounts)]
 pub struct MintToChecked1ofNMultisig<'info> {
@@ -33,4 +45,247 @@ pub fn mint_to_checked_1_of_n_multisig<'info>(
         ctx.signer_seeds,
     )
     .map_err(Into::into)
-}
\ No newline at end of file
+}
+
+/// Resize a withdraw request account to zero bytes and refund rent to the receiver.
+/// The empty account is kept so its address cannot be initialized again.
+pub fn close_request_account<'info>(
+    withdraw_request: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?;
+    let min_balance_for_zero = rent.minimum_balance(0);
+    let current_lamports = withdraw_request.lamports();
+
+    // Resize to zero first
+    withdraw_request.resize(0)?;
+
+    // Calculate how much to refund (keeping minimum for 0-byte account)
+    if current_lamports > min_balance_for_zero {
+        let refund = current_lamports - min_balance_for_zero;
+        withdraw_request.sub_lamports(refund)?;
+        receiver.add_lamports(refund)?;
+    }
+    Ok(())
+}
+
+/// Grow an account created by an earlier program version to `space` bytes, with the payer covering
+/// the additional rent. Fields are only appended, so the new bytes start zeroed after the existing data.
+pub fn migrate_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    discriminator: &[u8],
+    space: usize,
+) -> Result<()> {
+    require_gt!(space, account.data_len(), SolvError::AlreadyMigrated);
+    require!(
+        account.try_borrow_data()?.starts_with(discriminator),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+
+    let rent = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+    if rent > 0 {
+        let accounts = Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        transfer(CpiContext::new(system_program.clone(), accounts), rent)?;
+    }
+    account.resize(space)?;
+    Ok(())
+}
+
+/// Transfer tokens out of a vault token account, signed by the vault PDA
+pub fn transfer_from_vault<'info>(
+    token_program: AccountInfo<'info>,
+    vault: &Account<'info, Vault>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    // Signer seeds
+    let key = vault.mint.key();
+    let bump = [vault.bump];
+    let signer_seeds: [&[&[u8]]; 1] = [&[b"vault", key.as_ref(), bump.as_ref()]];
+
+    let accounts = TransferChecked {
+        from,
+        to,
+        mint: mint.to_account_info(),
+        authority: vault.to_account_info(),
+    };
+
+    let ctx = CpiContext::new_with_signer(token_program, accounts, &signer_seeds);
+
+    transfer_checked(ctx, amount, mint.decimals)
+}
+
+/// Mint target shares through the 1-of-N multisig mint authority, signed by the vault PDA
+pub fn mint_from_vault<'info>(
+    token_program: AccountInfo<'info>,
+    vault: &Account<'info, Vault>,
+    mint: &InterfaceAccount<'info, Mint>,
+    multisig: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    // Signer seeds
+    let key = vault.mint.key();
+    let bump = [vault.bump];
+    let signer_seeds: [&[&[u8]]; 1] = [&[b"vault", key.as_ref(), bump.as_ref()]];
+
+    // For a 1/2 multisig, we only need 1 signature (the PDA)
+    let accounts = MintToChecked1ofNMultisig {
+        mint: mint.to_account_info(),
+        to,
+        multisig,
+        signer: vault.to_account_info(),
+    };
+
+    let ctx = CpiContext::new_with_signer(token_program, accounts, &signer_seeds);
+
+    mint_to_checked_1_of_n_multisig(ctx, amount, mint.decimals)
+}
+
+/// Create an empty withdraw request account at the PDA signed by `signer_seeds`, funded by the payer.
+/// Like Anchor's `init`, an address already holding lamports is topped up, allocated and assigned.
+pub fn create_request_account<'info>(
+    payer: &AccountInfo<'info>,
+    withdraw_request: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let space = WithdrawRequest::DISCRIMINATOR.len() + WithdrawRequest::INIT_SPACE;
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = [signer_seeds];
+
+    if withdraw_request.lamports().eq(&0) {
+        let accounts = CreateAccount {
+            from: payer.clone(),
+            to: withdraw_request.clone(),
+        };
+        return create_account(
+            CpiContext::new_with_signer(system_program.clone(), accounts, &signer),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let top_up = rent.saturating_sub(withdraw_request.lamports());
+    if top_up > 0 {
+        let accounts = Transfer {
+            from: payer.clone(),
+            to: withdraw_request.clone(),
+        };
+        transfer(CpiContext::new(system_program.clone(), accounts), top_up)?;
+    }
+    let accounts = Allocate {
+        account_to_allocate: withdraw_request.clone(),
+    };
+    allocate(CpiContext::new_with_signer(system_program.clone(), accounts, &signer), space as u64)?;
+    let accounts = Assign {
+        account_to_assign: withdraw_request.clone(),
+    };
+    assign(CpiContext::new_with_signer(system_program.clone(), accounts, &signer), &crate::ID)
+}
+
+/// Deserialize a withdraw request account
+pub fn load_request_account(withdraw_request: &AccountInfo) -> Result<WithdrawRequest> {
+    let mut withdraw_request_data = &withdraw_request.data.borrow()[..];
+    WithdrawRequest::try_deserialize(&mut withdraw_request_data)
+}
+
+/// Serialize a withdraw request back into its account
+pub fn save_request_account(withdraw_request: &AccountInfo, data: &WithdrawRequest) -> Result<()> {
+    let mut withdraw_request_data = withdraw_request.try_borrow_mut_data()?;
+    data.try_serialize(&mut &mut withdraw_request_data[..])
+}
+
+/// Fill a withdraw request by `amount` from the vault token account, pay the user and emit the
+/// withdraw event, then close the request account to the user once fully filled or save it.
+/// The fee is paid to the fee receiver token account if given, otherwise it is returned for the
+/// caller to pay. Returns the fee.
+#[allow(clippy::too_many_arguments)]
+pub fn pay_withdraw_request<'info>(
+    token_program: &AccountInfo<'info>,
+    vault: &Account<'info, Vault>,
+    mint_withdraw: &InterfaceAccount<'info, Mint>,
+    vault_withdraw_ta: &AccountInfo<'info>,
+    fee_receiver_ta: Option<&AccountInfo<'info>>,
+    user: &AccountInfo<'info>,
+    user_withdraw_ta: &AccountInfo<'info>,
+    withdraw_request_info: &AccountInfo<'info>,
+    withdraw_request: &mut WithdrawRequest,
+    amount: u64,
+) -> Result<u64> {
+    let (amount, fee, shortfall, filled) = vault.fill_withdraw_request(withdraw_request, amount)?;
+
+    // Withdraw fee
+    if let Some(fee_receiver_ta) = fee_receiver_ta {
+        transfer_from_vault(
+            token_program.clone(),
+            vault,
+            mint_withdraw,
+            vault_withdraw_ta.clone(),
+            fee_receiver_ta.clone(),
+            fee,
+        )?;
+    }
+
+    // Withdraw amount
+    transfer_from_vault(
+        token_program.clone(),
+        vault,
+        mint_withdraw,
+        vault_withdraw_ta.clone(),
+        user_withdraw_ta.clone(),
+        amount,
+    )?;
+
+    emit!(WithdrawEvent {
+        user: user.key(),
+        vault: vault.key(),
+        withdraw_amount: amount,
+        withdraw_token: mint_withdraw.key(),
+        sequence: withdraw_request.sequence,
+        withdraw_fee: fee,
+        shortfall,
+        filled_amount: withdraw_request.filled_amount,
+        remaining_amount: withdraw_request.remaining_amount()?,
+    });
+
+    // Close the request once fully filled, otherwise persist the filled amount
+    if filled {
+        close_request_account(withdraw_request_info, user)?;
+    } else {
+        save_request_account(withdraw_request_info, withdraw_request)?;
+    }
+    Ok(fee)
+}
+
+/// Ensure a withdraw destination not owned by the user is an active entry of the user's
+/// address book. Any destination is allowed if the user has no address book.
+pub fn check_withdraw_destination(
+    address_book: &AccountInfo,
+    user: &Pubkey,
+    destination: &InterfaceAccount<TokenAccount>,
+    delay: u32,
+) -> Result<()> {
+    if destination.owner.eq(user) || address_book.owner.ne(&crate::ID) || address_book.data_is_empty() {
+        return Ok(());
+    }
+    let mut address_book_data = &address_book.data.borrow()[..];
+    AddressBook::try_deserialize(&mut address_book_data)?.check_destination(&destination.key(), delay)
+}
+
+/// Load the verifier set of a vault, None if it has not been created
+pub fn load_verifier_set(verifier_set: &AccountInfo) -> Result<Option<VerifierSet>> {
+    if verifier_set.owner.ne(&crate::ID) || verifier_set.data_is_empty() {
+        return Ok(None);
+    }
+    let mut verifier_set_data = &verifier_set.data.borrow()[..];
+    Ok(Some(VerifierSet::try_deserialize(&mut verifier_set_data)?))
+}
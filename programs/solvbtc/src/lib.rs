@@ -9,6 +9,7 @@ pub mod state;
 pub mod helpers;
 
 use contexts::*;
+use state::{ExpiryPolicy, SettlementPolicy, VerifierSignature, VerifierType};
 
 declare_id!("soLv1S6GsAEVEnXmVY3oz6GtrNJteQ28iTyRQrHXvkz");
 
@@ -28,36 +29,32 @@ pub mod solvbtc {
         min_amount_out: u64,
     ) -> Result<()> {
         ctx.accounts.deposit_tokens(amount)?;
-        ctx.accounts.mint_target_tokens(amount, min_amount_out)
+        ctx.accounts.mint_target_tokens(amount, min_amount_out, ctx.bumps.user_deposit)
     }
 
-    // withdraw_request(shares, request hash)
-    // Check request hash has not been requested or withdrawn. Apply a second hash as the key instead of using the request hash directly, for security purposes.
-    // The second hash includes (user, withdraw token address,request hash, shares, NAV)
-    // Record the second hash (hash, status)
+    // withdraw_request(shares)
+    // Assign the next sequence number of the vault to the request and record it at (vault, sequence).
+    // The verifier signs a hash of (user, withdraw token address, token address, sequence, shares, NAV)
     // User transfer target token to vault, vault burn the token
-    // emit event  WithdrawRequest(user, withdraw token, shares  token address, shares, request hash, current NAV)
+    // emit event  WithdrawRequest(user, withdraw token, shares  token address, shares, sequence, current NAV)
     #[instruction(discriminator = 1)]
     #[doc = "# Withdraw Request\nEnable user to request a withdrawal in a certain currency and record it onchain."]
     pub fn vault_withdraw_request(
         ctx: Context<VaultRequestWithdraw>,
-        request_hash: [u8; 32],
         amount: u64,
     ) -> Result<()> {
         ctx.accounts.burn_tokens(amount)?;
-        ctx.accounts
-            .open_request_account(request_hash, amount)
+        ctx.accounts.open_request_account(amount)
     }
 
     #[instruction(discriminator = 2)]
     #[doc = "# Withdraw\nEnable user to process a withdrawal with a signed withdraw request."]
     pub fn vault_withdraw(
         ctx: Context<VaultWithdraw>,
-        _hash: [u8; 32],
-        signature: [u8; 64],
+        _sequence: u64,
+        signatures: Vec<VerifierSignature>,
     ) -> Result<()> {
-        ctx.accounts.withdraw_tokens(signature)?;
-        ctx.accounts.close_request_account()
+        ctx.accounts.withdraw_tokens(signatures)
     }
 
     #[instruction(discriminator = 3)]
@@ -71,6 +68,7 @@ pub mod solvbtc {
         oracle_manager: Pubkey,
         nav: u64,
         withdraw_fee: u16,
+        chain_id: u64,
     ) -> Result<()> {
         ctx.accounts.initialize(
             admin,
@@ -80,6 +78,7 @@ pub mod solvbtc {
             oracle_manager,
             nav,
             withdraw_fee,
+            chain_id,
             ctx.bumps.vault,
         )
     }
@@ -91,9 +90,9 @@ pub mod solvbtc {
     }
 
     #[instruction(discriminator = 5)]
-    #[doc = "# Add Vault Currency\nEnable admin to add deposit currency to vault."]
-    pub fn vault_add_currency(ctx: Context<VaultUpdate>, currency: Pubkey, deposit_fee: u16) -> Result<()> {
-        ctx.accounts.add_currency(currency, deposit_fee)
+    #[doc = "# Add Vault Currency\nEnable admin to add deposit currency to vault with its initial exchange rate."]
+    pub fn vault_add_currency(ctx: Context<VaultAddCurrency>, deposit_fee: u16, rate: u64) -> Result<()> {
+        ctx.accounts.add_currency(deposit_fee, rate)
     }
 
     #[instruction(discriminator = 6)]
@@ -189,4 +188,301 @@ pub mod solvbtc {
     ) -> Result<()> {
         ctx.accounts.transfer_admin(admin)
     }
+
+    #[instruction(discriminator = 19)]
+    #[doc = "# Set Currency Rate\nEnable oracle manager to update the exchange rate of a deposit currency."]
+    pub fn vault_set_currency_rate(ctx: Context<VaultOracleUpdate>, currency: Pubkey, rate: u64) -> Result<()> {
+        ctx.accounts.set_currency_rate(currency, rate)
+    }
+
+    #[instruction(discriminator = 20)]
+    #[doc = "# Set Vault Max Total Shares\nEnable admin to cap the total supply of the target token minted by deposits, 0 to disable."]
+    pub fn vault_set_max_total_shares(ctx: Context<VaultUpdate>, max_total_shares: u64) -> Result<()> {
+        ctx.accounts.set_max_total_shares(max_total_shares)
+    }
+
+    #[instruction(discriminator = 21)]
+    #[doc = "# Set Vault Deposit Cap\nEnable admin to cap cumulative deposits of a currency, 0 to disable."]
+    pub fn vault_set_deposit_cap(ctx: Context<VaultUpdate>, currency: Pubkey, deposit_cap: u64) -> Result<()> {
+        ctx.accounts.set_deposit_cap(currency, deposit_cap)
+    }
+
+    #[instruction(discriminator = 22)]
+    #[doc = "# Set Vault Max User Shares\nEnable admin to cap the shares minted to a single user, 0 to disable."]
+    pub fn vault_set_max_user_shares(ctx: Context<VaultUpdate>, max_user_shares: u64) -> Result<()> {
+        ctx.accounts.set_max_user_shares(max_user_shares)
+    }
+
+    #[instruction(discriminator = 23)]
+    #[doc = "# Set Vault Paused\nEnable admin or guardian to pause vault operations. Only the admin may unpause."]
+    pub fn vault_set_paused(ctx: Context<VaultPause>, paused: u8) -> Result<()> {
+        ctx.accounts.set_paused(paused)
+    }
+
+    #[instruction(discriminator = 24)]
+    #[doc = "# Set Vault Guardian\nEnable admin to set vault guardian."]
+    pub fn vault_set_guardian(ctx: Context<VaultUpdate>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.set_guardian(guardian)
+    }
+
+    #[instruction(discriminator = 25)]
+    #[doc = "# Set Minter Manager Paused\nEnable admin or guardian to pause minting. Only the admin may unpause."]
+    pub fn minter_manager_set_paused(ctx: Context<MinterManagerPause>, paused: u8) -> Result<()> {
+        ctx.accounts.set_paused(paused)
+    }
+
+    #[instruction(discriminator = 26)]
+    #[doc = "# Set Minter Manager Guardian\nEnable admin to set minter manager guardian."]
+    pub fn minter_manager_set_guardian(
+        ctx: Context<MinterManagerTransferAdmin>,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.set_guardian(guardian)
+    }
+
+    #[instruction(discriminator = 27)]
+    #[doc = "# Set Vault Max NAV Age\nEnable admin to set the maximum NAV age in seconds for deposits and withdraw requests, 0 to disable."]
+    pub fn vault_set_max_nav_age(ctx: Context<VaultUpdate>, max_nav_age: u32) -> Result<()> {
+        ctx.accounts.set_max_nav_age(max_nav_age)
+    }
+
+    #[instruction(discriminator = 28)]
+    #[doc = "# Cancel Withdraw Request\nEnable user to cancel a pending withdraw request and have its shares minted back."]
+    pub fn vault_cancel_withdraw_request(
+        ctx: Context<VaultCancelWithdrawRequest>,
+        _sequence: u64,
+    ) -> Result<()> {
+        ctx.accounts.cancel_request()?;
+        ctx.accounts.close_request_account()
+    }
+
+    #[instruction(discriminator = 29)]
+    #[doc = "# Set Vault Cancel Delay\nEnable admin to set the minimum age in seconds of a withdraw request before it can be cancelled."]
+    pub fn vault_set_cancel_delay(ctx: Context<VaultUpdate>, cancel_delay: u32) -> Result<()> {
+        ctx.accounts.set_cancel_delay(cancel_delay)
+    }
+
+    #[instruction(discriminator = 30)]
+    #[doc = "# Partial Withdraw\nEnable user to process part of a withdrawal with a signed fill amount."]
+    pub fn vault_withdraw_partial(
+        ctx: Context<VaultWithdraw>,
+        _sequence: u64,
+        amount: u64,
+        signatures: Vec<VerifierSignature>,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_partial(amount, signatures)
+    }
+
+    #[instruction(discriminator = 31)]
+    #[doc = "# Expire Withdraw Request\nEnable anyone to close an expired withdraw request and mint its unfilled shares according to the vault expiry policy."]
+    pub fn vault_expire_withdraw_request(
+        ctx: Context<VaultExpireWithdrawRequest>,
+        _sequence: u64,
+    ) -> Result<()> {
+        ctx.accounts.expire_request()?;
+        ctx.accounts.close_request_account()
+    }
+
+    #[instruction(discriminator = 32)]
+    #[doc = "# Set Vault Withdraw Expiry\nEnable admin to set the lifetime in seconds of withdraw requests and the policy for expired requests."]
+    pub fn vault_set_withdraw_expiry(
+        ctx: Context<VaultUpdate>,
+        withdraw_request_ttl: u32,
+        expiry_policy: ExpiryPolicy,
+    ) -> Result<()> {
+        ctx.accounts.set_withdraw_expiry(withdraw_request_ttl, expiry_policy)
+    }
+
+    #[instruction(discriminator = 33)]
+    #[doc = "# Withdraw For\nEnable anyone to process a signed withdrawal on behalf of the user to the token account recorded in the request."]
+    pub fn vault_withdraw_for(
+        ctx: Context<VaultWithdrawFor>,
+        _sequence: u64,
+        signatures: Vec<VerifierSignature>,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_tokens(signatures)
+    }
+
+    #[instruction(discriminator = 34)]
+    #[doc = "# Withdraw Batch\nEnable anyone to process many signed withdrawals in one currency, passing each withdraw request, user withdraw token account and user as remaining accounts."]
+    pub fn vault_withdraw_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, VaultWithdrawBatch<'info>>,
+        approvals: Vec<WithdrawApproval>,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_batch(approvals, ctx.remaining_accounts)
+    }
+
+    #[instruction(discriminator = 35)]
+    #[doc = "# Advance Withdraw Queue\nEnable anyone to move the withdraw queue head past completed withdraw requests, passed in order as remaining accounts."]
+    pub fn vault_advance_withdraw_queue<'info>(
+        ctx: Context<'_, '_, '_, 'info, VaultAdvanceWithdrawQueue<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.advance_queue(ctx.remaining_accounts)
+    }
+
+    #[instruction(discriminator = 36)]
+    #[doc = "# Set Vault Epoch Mode\nEnable admin to settle new withdraw requests at the NAV of their redemption epoch."]
+    pub fn vault_set_epoch_mode(ctx: Context<VaultUpdate>, epoch_mode: bool) -> Result<()> {
+        ctx.accounts.set_epoch_mode(epoch_mode)
+    }
+
+    #[instruction(discriminator = 37)]
+    #[doc = "# Close Redemption Epoch\nEnable oracle manager to close the current redemption epoch with its settlement NAV."]
+    pub fn vault_close_redemption_epoch(ctx: Context<VaultCloseRedemptionEpoch>, nav: u64) -> Result<()> {
+        ctx.accounts.close_epoch(nav, ctx.bumps.redemption_epoch)
+    }
+
+    #[instruction(discriminator = 38)]
+    #[doc = "# Set Vault Settlement Policy\nEnable admin to pay withdrawals at the request NAV or at the lower of the request NAV and the current NAV."]
+    pub fn vault_set_settlement_policy(
+        ctx: Context<VaultUpdate>,
+        settlement_policy: SettlementPolicy,
+    ) -> Result<()> {
+        ctx.accounts.set_settlement_policy(settlement_policy)
+    }
+
+    #[instruction(discriminator = 39)]
+    #[doc = "# Set Vault Withdraw NAV Tolerance\nEnable admin to set the tolerance in basis points of the request NAV above the current NAV at withdrawal."]
+    pub fn vault_set_withdraw_nav_tolerance(ctx: Context<VaultUpdate>, withdraw_nav_tolerance: u16) -> Result<()> {
+        ctx.accounts.set_withdraw_nav_tolerance(withdraw_nav_tolerance)
+    }
+
+    #[instruction(discriminator = 40)]
+    #[doc = "# Set Vault Max NAV Change\nEnable admin to set the maximum change in basis points of the NAV and currency rates per update."]
+    pub fn vault_set_max_nav_change(ctx: Context<VaultUpdate>, max_nav_change: u16) -> Result<()> {
+        ctx.accounts.set_max_nav_change(max_nav_change)
+    }
+
+    #[instruction(discriminator = 41)]
+    #[doc = "# Switch Withdraw Currency\nEnable user or anyone holding a verifier signature to move the unfilled amount of a withdraw request to a new request paid out in another currency."]
+    pub fn vault_switch_withdraw_currency(
+        ctx: Context<VaultSwitchWithdrawCurrency>,
+        _sequence: u64,
+        signatures: Vec<VerifierSignature>,
+    ) -> Result<()> {
+        ctx.accounts.switch_currency(signatures)?;
+        ctx.accounts.close_request_account()
+    }
+
+    #[instruction(discriminator = 42)]
+    #[doc = "# Instant Redeem\nEnable user to burn shares and withdraw immediately from vault liquidity within the instant redemption cap."]
+    pub fn vault_instant_redeem(
+        ctx: Context<VaultInstantRedeem>,
+        amount: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        ctx.accounts.instant_redeem(amount, min_amount_out)
+    }
+
+    #[instruction(discriminator = 43)]
+    #[doc = "# Set Vault Instant Redeem\nEnable admin to set the instant redemption fee in basis points and the cap on shares redeemed per window, 0 to disable."]
+    pub fn vault_set_instant_redeem(
+        ctx: Context<VaultUpdate>,
+        instant_redeem_fee: u16,
+        instant_redeem_cap: u64,
+        instant_redeem_window: u32,
+    ) -> Result<()> {
+        ctx.accounts.set_instant_redeem(instant_redeem_fee, instant_redeem_cap, instant_redeem_window)
+    }
+
+    #[instruction(discriminator = 44)]
+    #[doc = "# Add Address Book Entry\nEnable user to add a withdraw destination to their address book, usable once the vault address book delay has elapsed."]
+    pub fn vault_add_address_book_entry(ctx: Context<VaultUpdateAddressBook>, token_account: Pubkey) -> Result<()> {
+        ctx.accounts.add_entry(token_account, ctx.bumps.address_book)
+    }
+
+    #[instruction(discriminator = 45)]
+    #[doc = "# Remove Address Book Entry\nEnable user to remove a withdraw destination from their address book."]
+    pub fn vault_remove_address_book_entry(ctx: Context<VaultUpdateAddressBook>, token_account: Pubkey) -> Result<()> {
+        ctx.accounts.remove_entry(token_account)
+    }
+
+    #[instruction(discriminator = 46)]
+    #[doc = "# Set Vault Address Book Delay\nEnable admin to set the delay in seconds before a new address book entry can receive withdrawals."]
+    pub fn vault_set_address_book_delay(ctx: Context<VaultUpdate>, address_book_delay: u32) -> Result<()> {
+        ctx.accounts.set_address_book_delay(address_book_delay)
+    }
+
+    #[instruction(discriminator = 48)]
+    #[doc = "# Set Vault EIP-712 Verifier\nEnable admin to approve withdrawals with EIP-712 signatures from an Ethereum address."]
+    pub fn vault_set_eip712_verifier(ctx: Context<VaultUpdate>, address: [u8; 20]) -> Result<()> {
+        ctx.accounts.set_eip712_verifier(address)
+    }
+
+    #[instruction(discriminator = 49)]
+    #[doc = "# Add Vault Verifier Set Member\nEnable admin to add a key, in the format of its verifier type, to the verifier set approving withdrawals."]
+    pub fn vault_add_verifier(ctx: Context<VaultUpdateVerifierSet>, verifier: [u8; 64], verifier_type: VerifierType) -> Result<()> {
+        ctx.accounts.add_verifier(verifier, verifier_type, ctx.bumps.verifier_set)
+    }
+
+    #[instruction(discriminator = 50)]
+    #[doc = "# Remove Vault Verifier Set Member\nEnable admin to remove a key from the verifier set approving withdrawals."]
+    pub fn vault_remove_verifier(ctx: Context<VaultUpdateVerifierSet>, verifier: [u8; 64]) -> Result<()> {
+        ctx.accounts.remove_verifier(verifier)
+    }
+
+    #[instruction(discriminator = 51)]
+    #[doc = "# Set Vault Verifier Threshold\nEnable admin to set the number of verifier set members that must sign each withdrawal, 0 to approve with the vault verifier alone."]
+    pub fn vault_set_verifier_threshold(ctx: Context<VaultUpdateVerifierSet>, threshold: u8) -> Result<()> {
+        ctx.accounts.set_threshold(threshold)
+    }
+
+    #[instruction(discriminator = 52)]
+    #[doc = "# Set Vault Recover Verifier\nEnable admin to set the ECDSA public key used for withdrawal signature verification, checked with the secp256k1_recover syscall."]
+    pub fn vault_set_recover_verifier(ctx: Context<VaultUpdate>, verifier: [u8; 64]) -> Result<()> {
+        ctx.accounts.set_recover_verifier(verifier)
+    }
+
+    #[instruction(discriminator = 53)]
+    #[doc = "# Set Vault Ed25519 Verifier\nEnable admin to approve withdrawals with Ed25519 signatures checked by an Ed25519 program instruction in the same transaction."]
+    pub fn vault_set_ed25519_verifier(ctx: Context<VaultUpdate>, verifier: Pubkey) -> Result<()> {
+        ctx.accounts.set_ed25519_verifier(verifier)
+    }
+
+    #[instruction(discriminator = 54)]
+    #[doc = "# Set Vault Schnorr Verifier\nEnable admin to approve withdrawals with BIP-340 Schnorr signatures by an x-only key."]
+    pub fn vault_set_schnorr_verifier(ctx: Context<VaultUpdate>, verifier: [u8; 32]) -> Result<()> {
+        ctx.accounts.set_schnorr_verifier(verifier)
+    }
+
+    #[instruction(discriminator = 55)]
+    #[doc = "# Stage Vault Verifier\nEnable admin to stage a verifier that replaces the current one at the activation time, keeping the current one accepted for the overlap window."]
+    pub fn vault_stage_verifier(
+        ctx: Context<VaultUpdate>,
+        verifier: [u8; 64],
+        verifier_type: VerifierType,
+        activates_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.stage_verifier(verifier, verifier_type, activates_at)
+    }
+
+    #[instruction(discriminator = 56)]
+    #[doc = "# Set Vault Verifier Overlap\nEnable admin to set the time in seconds a replaced verifier stays accepted after a staged verifier activates."]
+    pub fn vault_set_verifier_overlap(ctx: Context<VaultUpdate>, verifier_overlap: u32) -> Result<()> {
+        ctx.accounts.set_verifier_overlap(verifier_overlap)
+    }
+
+    #[instruction(discriminator = 57)]
+    #[doc = "# Migrate Vault\nEnable admin to resize a vault created by an earlier program version to the current layout, passing its deposit currency mints in order as remaining accounts."]
+    pub fn vault_migrate<'info>(ctx: Context<'_, '_, 'info, 'info, VaultMigrate<'info>>, chain_id: u64) -> Result<()> {
+        ctx.accounts.migrate(chain_id, ctx.remaining_accounts)
+    }
+
+    #[instruction(discriminator = 58)]
+    #[doc = "# Migrate Minter Manager\nEnable admin to resize a minter manager created by an earlier program version to the current layout."]
+    pub fn minter_manager_migrate(ctx: Context<MinterManagerMigrate>) -> Result<()> {
+        ctx.accounts.migrate()
+    }
+
+    #[instruction(discriminator = 59)]
+    #[doc = "# Set Vault Instant Redeem Reserve\nEnable admin to hold back vault liquidity of a currency from instant redemptions for pending withdraw requests."]
+    pub fn vault_set_instant_redeem_reserve(ctx: Context<VaultUpdate>, currency: Pubkey, instant_redeem_reserve: u64) -> Result<()> {
+        ctx.accounts.set_instant_redeem_reserve(currency, instant_redeem_reserve)
+    }
+
+    #[instruction(discriminator = 60)]
+    #[doc = "# Set Vault Chain ID\nEnable admin to set the chain ID bound into signed withdraw messages."]
+    pub fn vault_set_chain_id(ctx: Context<VaultUpdate>, chain_id: u64) -> Result<()> {
+        ctx.accounts.set_chain_id(chain_id)
+    }
 }
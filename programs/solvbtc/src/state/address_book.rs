@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::SolvError;
+
+#[account(discriminator = [6])]
+#[derive(InitSpace)]
+pub struct AddressBook {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub entries: [AddressBookEntry; 10],
+    pub bump: u8,
+}
+
+#[derive(Default, Clone, Copy, InitSpace, AnchorSerialize, AnchorDeserialize)]
+pub struct AddressBookEntry {
+    token_account: Pubkey,
+    added_at: i64,
+}
+
+impl AddressBook {
+    pub fn add_entry(&mut self, vault: Pubkey, user: Pubkey, token_account: Pubkey, bump: u8) -> Result<()> {
+        // Initialize on first entry
+        if self.vault.eq(&Pubkey::default()) {
+            self.vault = vault;
+            self.user = user;
+            self.bump = bump;
+        }
+
+        if self.entries.iter().any(|entry| entry.token_account.eq(&token_account)) {
+            return Err(SolvError::AddressBookEntryExists.into());
+        }
+
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.token_account.eq(&Pubkey::default()))
+            .ok_or(SolvError::AddressBookFull)?;
+        self.entries[index] = AddressBookEntry {
+            token_account,
+            added_at: Clock::get()?.unix_timestamp,
+        };
+        Ok(())
+    }
+
+    pub fn remove_entry(&mut self, token_account: Pubkey) -> Result<()> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.token_account.eq(&token_account))
+            .ok_or(SolvError::AddressBookEntryNotFound)?;
+        self.entries[index] = AddressBookEntry::default();
+        Ok(())
+    }
+
+    /// Ensure the token account was added to the address book at least `delay` seconds ago
+    pub fn check_destination(&self, token_account: &Pubkey, delay: u32) -> Result<()> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.token_account.eq(token_account))
+            .ok_or(SolvError::InvalidWithdrawDestination)?;
+        let active_at = entry.added_at.checked_add(delay.into()).ok_or(ProgramError::ArithmeticOverflow)?;
+        require_gte!(Clock::get()?.unix_timestamp, active_at, SolvError::InvalidWithdrawDestination);
+        Ok(())
+    }
+}
@@ -9,6 +9,8 @@ pub struct MinterManager {
     pub minters: [Pubkey; 10],
     pub updated: i64,
     pub bump: u8,
+    pub guardian: Pubkey,
+    pub paused: u8,
 }
 
 impl MinterManager {
@@ -29,6 +31,24 @@ impl MinterManager {
         self.update()
     }
 
+    pub fn set_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        self.guardian = guardian;
+        self.update()
+    }
+
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.paused & flag != 0
+    }
+
+    /// Set pause flags. The guardian may only add flags, the admin may set any.
+    pub fn set_paused(&mut self, authority: &Pubkey, paused: u8) -> Result<()> {
+        if authority.ne(&self.admin) {
+            require!(paused & self.paused == self.paused, SolvError::GuardianCannotUnpause);
+        }
+        self.paused = paused;
+        self.update()
+    }
+
     pub fn add_minter(&mut self, minter: Pubkey) -> Result<()> {
         // Ensure we are not trying to add a null address
         if minter.eq(&Pubkey::default()) {
@@ -6,3 +6,15 @@ pub use minter_manager::*;
 
 pub mod withdraw_request;
 pub use withdraw_request::*;
+
+pub mod user_deposit;
+pub use user_deposit::*;
+
+pub mod redemption_epoch;
+pub use redemption_epoch::*;
+
+pub mod address_book;
+pub use address_book::*;
+
+pub mod verifier_set;
+pub use verifier_set::*;
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+#[account(discriminator = [5])]
+#[derive(InitSpace)]
+pub struct RedemptionEpoch {
+    pub vault: Pubkey,
+    pub epoch: u64,
+    /// NAV at which withdraw requests of the epoch are settled
+    pub nav: u64,
+    pub closed_at: i64,
+    pub bump: u8,
+}
+
+impl RedemptionEpoch {
+    pub fn initialize(&mut self, vault: Pubkey, epoch: u64, nav: u64, bump: u8) -> Result<()> {
+        *self = RedemptionEpoch {
+            vault,
+            epoch,
+            nav,
+            closed_at: Clock::get()?.unix_timestamp,
+            bump,
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::SolvError;
+
+#[account(discriminator = [4])]
+#[derive(InitSpace)]
+pub struct UserDeposit {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    /// Cumulative shares minted to the user by deposits, never decremented
+    pub shares: u64,
+    pub bump: u8,
+}
+
+impl UserDeposit {
+    /// Record shares minted to the user and enforce the per-user cap if one is set
+    pub fn deposit(&mut self, vault: Pubkey, user: Pubkey, shares: u64, max_shares: u64, bump: u8) -> Result<()> {
+        // Initialize on first deposit
+        if self.vault.eq(&Pubkey::default()) {
+            self.vault = vault;
+            self.user = user;
+            self.bump = bump;
+        }
+
+        self.shares = self.shares.checked_add(shares).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if max_shares > 0 {
+            require_gte!(max_shares, self.shares, SolvError::UserCapExceeded);
+        }
+        Ok(())
+    }
+}
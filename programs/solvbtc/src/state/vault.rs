@@ -1,6 +1,7 @@
 use anchor_lang::prelude::{borsh::de, *};
 
-use crate::{constants::{MAX_FEE, ONE_BITCOIN}, errors::SolvError};
+use crate::{constants::{DEFAULT_MAX_NAV_CHANGE, DEFAULT_WITHDRAW_NAV_TOLERANCE, MAX_FEE, ONE_BITCOIN}, errors::SolvError, events::{VerifierActivated, VerifierRetired, VerifierStaged}, state::{MessageVersion, RedemptionEpoch, VerifierSet, VerifierSignature, WithdrawRequest}};
+use solana_secp256k1_ecdsa::Secp256k1EcdsaSignature;
 
 #[account(discriminator = [1])]
 #[derive(InitSpace)]
@@ -16,12 +17,132 @@ pub struct Vault {
     pub nav: u64,
     pub withdraw_fee: u16,
     pub bump: u8,
+    /// Maximum supply of the target mint, 0 if uncapped
+    pub max_total_shares: u64,
+    /// Maximum shares ever minted to a single user by deposits, 0 if uncapped. This is a lifetime
+    /// cap: shares later withdrawn or transferred are still counted.
+    pub max_user_shares: u64,
+    pub guardian: Pubkey,
+    pub paused: u8,
+    /// Time of the last NAV update by the oracle manager
+    pub nav_updated: i64,
+    /// Maximum age of the NAV in seconds for deposits and withdraw requests, 0 if unchecked
+    pub max_nav_age: u32,
+    /// Minimum age in seconds of a withdraw request before the user may cancel it
+    pub cancel_delay: u32,
+    /// Lifetime in seconds of a withdraw request, 0 if requests never expire
+    pub withdraw_request_ttl: u32,
+    pub expiry_policy: ExpiryPolicy,
+    /// Sequence number assigned to the next withdraw request
+    pub withdraw_request_count: u64,
+    /// Sequence number of the oldest withdraw request that may still be pending
+    pub withdraw_queue_head: u64,
+    /// Whether withdraw requests are settled at the NAV of their redemption epoch
+    pub epoch_mode: bool,
+    /// Redemption epoch new withdraw requests join in epoch mode
+    pub current_epoch: u64,
+    pub settlement_policy: SettlementPolicy,
+    /// Tolerance in basis points of the request NAV above the current NAV at withdrawal
+    pub withdraw_nav_tolerance: u16,
+    /// Maximum change in basis points of the NAV and currency rates per update
+    pub max_nav_change: u16,
+    /// Fee in basis points charged on instant redemptions
+    pub instant_redeem_fee: u16,
+    /// Maximum shares redeemed instantly per window, 0 if instant redemption is disabled
+    pub instant_redeem_cap: u64,
+    /// Length in seconds of the instant redemption window
+    pub instant_redeem_window: u32,
+    pub instant_redeem_window_start: i64,
+    /// Shares redeemed instantly in the current window
+    pub instant_redeemed: u64,
+    /// Time in seconds before a new address book entry can receive withdrawals
+    pub address_book_delay: u32,
+    pub verifier_type: VerifierType,
+    /// Verifier staged to replace the current one, in the key format of its type
+    pub pending_verifier: [u8; 64],
+    pub pending_verifier_type: VerifierType,
+    /// Time the staged verifier becomes active, 0 if none is staged
+    pub verifier_activation: i64,
+    /// Time in seconds the replaced verifier stays accepted after a staged verifier activates
+    pub verifier_overlap: u32,
+    /// Verifier replaced by the last rotation
+    pub previous_verifier: [u8; 64],
+    pub previous_verifier_type: VerifierType,
+    /// Time the previous verifier is retired, 0 once retired
+    pub previous_verifier_expiry: i64,
+    /// Decimals of the target mint
+    pub decimals: u8,
+    /// Decimals, rate and deposit cap of each deposit currency, indexed like deposit_currencies
+    pub currency_configs: [CurrencyConfig; 10],
+    /// Identifier of the deployment bound into signed withdraw messages, so signatures cannot be
+    /// replayed across clusters
+    pub chain_id: u64,
+}
+
+/// Scheme used by the verifier to approve withdrawals
+#[derive(Clone, Copy, PartialEq, Eq, InitSpace, AnchorSerialize, AnchorDeserialize)]
+pub enum VerifierType {
+    /// ECDSA signature over a SHA-256 message, the verifier is an uncompressed public key
+    Secp256k1Ecdsa,
+    /// Same signatures as Secp256k1Ecdsa, checked with the secp256k1_recover syscall
+    Secp256k1Recover,
+    /// Ethereum signature over EIP-712 typed data, the verifier holds a 20-byte address
+    Eip712,
+    /// Ed25519 signature checked by a preceding Ed25519 program instruction, the verifier holds a 32-byte key
+    Ed25519,
+    /// BIP-340 Schnorr signature, the verifier holds a 32-byte x-only key
+    Schnorr,
+}
+
+impl VerifierType {
+    /// Ensure a verifier key is nonzero and in the format of this type, with unused bytes zeroed
+    pub fn check_key(&self, key: &[u8; 64]) -> Result<()> {
+        require!(key.ne(&[0u8; 64]), SolvError::InvalidAddress);
+        let len = match self {
+            VerifierType::Secp256k1Ecdsa | VerifierType::Secp256k1Recover => 64,
+            VerifierType::Ed25519 | VerifierType::Schnorr => 32,
+            VerifierType::Eip712 => 20,
+        };
+        require!(key[len..].iter().all(|byte| byte.eq(&0)), SolvError::InvalidVerifierKey);
+        Ok(())
+    }
+}
+
+/// Recipient of the unfilled shares of an expired withdraw request
+#[derive(Clone, Copy, PartialEq, Eq, InitSpace, AnchorSerialize, AnchorDeserialize)]
+pub enum ExpiryPolicy {
+    /// Shares are minted back to the user
+    RestoreShares,
+    /// Shares are minted to the treasurer to settle the claim off-chain
+    Treasurer,
+}
+
+/// NAV at which withdraw requests are paid out
+#[derive(Clone, Copy, PartialEq, Eq, InitSpace, AnchorSerialize, AnchorDeserialize)]
+pub enum SettlementPolicy {
+    /// Requests are paid at the NAV recorded when they were made
+    RequestNav,
+    /// Requests are paid at the lower of the request NAV and the current NAV
+    LowerNav,
 }
 
 #[derive(Default, Clone, Copy, InitSpace, AnchorSerialize, AnchorDeserialize)]
 pub struct WhitelistedToken {
     mint: Pubkey,
-    deposit_fee: u16
+    deposit_fee: u16,
+}
+
+#[derive(Default, Clone, Copy, InitSpace, AnchorSerialize, AnchorDeserialize)]
+pub struct CurrencyConfig {
+    decimals: u8,
+    /// Value of one whole unit of the currency in BTC, scaled by ONE_BITCOIN
+    rate: u64,
+    /// Maximum cumulative deposits of the currency, 0 if uncapped. This is a lifetime cap that
+    /// withdrawals do not free up.
+    deposit_cap: u64,
+    total_deposited: u64,
+    /// Vault liquidity of the currency held back from instant redemptions for pending withdraw requests
+    instant_redeem_reserve: u64,
 }
 
 impl Vault {
@@ -30,16 +151,20 @@ impl Vault {
         &mut self,
         admin: Pubkey,
         mint: Pubkey,
+        decimals: u8,
         fee_receiver: Pubkey,
         treasurer: Pubkey,
         verifier: [u8; 64],
         oracle_manager: Pubkey,
         nav: u64,
         withdraw_fee: u16,
+        chain_id: u64,
         bump: u8,
     ) -> Result<()> {
         require_gte!(nav, ONE_BITCOIN, SolvError::InvalidNAVValue);
         require_gte!(MAX_FEE, withdraw_fee, SolvError::InvalidFeeRatio);
+        require_gt!(chain_id, 0, SolvError::InvalidChainId);
+        let now = Clock::get()?.unix_timestamp;
         *self = Vault {
             admin,
             mint,
@@ -47,21 +172,92 @@ impl Vault {
             treasurer,
             verifier,
             deposit_currencies: [WhitelistedToken::default(); 10],
-            oracle_updated: Clock::get()?.unix_timestamp,
+            oracle_updated: now,
             oracle_manager,
             nav,
             withdraw_fee,
             bump,
+            max_total_shares: 0,
+            max_user_shares: 0,
+            guardian: Pubkey::default(),
+            paused: 0,
+            nav_updated: now,
+            max_nav_age: 0,
+            cancel_delay: 0,
+            withdraw_request_ttl: 0,
+            expiry_policy: ExpiryPolicy::RestoreShares,
+            withdraw_request_count: 0,
+            withdraw_queue_head: 0,
+            epoch_mode: false,
+            current_epoch: 0,
+            settlement_policy: SettlementPolicy::RequestNav,
+            withdraw_nav_tolerance: DEFAULT_WITHDRAW_NAV_TOLERANCE,
+            max_nav_change: DEFAULT_MAX_NAV_CHANGE,
+            instant_redeem_fee: 0,
+            instant_redeem_cap: 0,
+            instant_redeem_window: 0,
+            instant_redeem_window_start: 0,
+            instant_redeemed: 0,
+            address_book_delay: 0,
+            verifier_type: VerifierType::Secp256k1Ecdsa,
+            pending_verifier: [0u8; 64],
+            pending_verifier_type: VerifierType::Secp256k1Ecdsa,
+            verifier_activation: 0,
+            verifier_overlap: 0,
+            previous_verifier: [0u8; 64],
+            previous_verifier_type: VerifierType::Secp256k1Ecdsa,
+            previous_verifier_expiry: 0,
+            decimals,
+            currency_configs: [CurrencyConfig::default(); 10],
+            chain_id,
         };
         Ok(())
     }
 
+    /// Fill the fields added since the vault was created, once its account has been resized to the
+    /// current layout. Existing currencies, listed in order with their decimals, keep the 1:1 BTC rate.
+    pub fn migrate(&mut self, decimals: u8, chain_id: u64, currencies: &[(Pubkey, u8)]) -> Result<()> {
+        require_gt!(chain_id, 0, SolvError::InvalidChainId);
+        let listed = self.deposit_currencies.iter().take_while(|token| token.mint.ne(&Pubkey::default())).count();
+        require_eq!(currencies.len(), listed, SolvError::CurrencyNotFound);
+
+        self.decimals = decimals;
+        self.chain_id = chain_id;
+        // The NAV was last set no later than the last vault update
+        self.nav_updated = self.oracle_updated;
+        self.withdraw_nav_tolerance = DEFAULT_WITHDRAW_NAV_TOLERANCE;
+        self.max_nav_change = DEFAULT_MAX_NAV_CHANGE;
+        for (index, (mint, decimals)) in currencies.iter().enumerate() {
+            require_keys_eq!(self.deposit_currencies[index].mint, *mint, SolvError::CurrencyNotFound);
+            self.currency_configs[index] = CurrencyConfig { decimals: *decimals, rate: ONE_BITCOIN, ..Default::default() };
+        }
+        self.update()
+    }
+
     pub fn is_whitelisted(&self, mint: &Pubkey) -> bool {
         self.deposit_currencies.iter().find(|token| {
             token.mint.eq(mint)
         }).is_some()
     }
 
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.paused & flag != 0
+    }
+
+    /// Set pause flags. The guardian may only add flags, the admin may set any.
+    pub fn set_paused(&mut self, authority: &Pubkey, paused: u8) -> Result<()> {
+        if authority.ne(&self.admin) {
+            require!(paused & self.paused == self.paused, SolvError::GuardianCannotUnpause);
+        }
+        self.paused = paused;
+        self.update()
+    }
+
+    pub fn set_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        self.guardian = guardian;
+        self.update()
+    }
+
     pub fn update(&mut self) -> Result<()> {
         self.oracle_updated = Clock::get()?.unix_timestamp;
         Ok(())
@@ -74,11 +270,159 @@ impl Vault {
 
     pub fn set_deposit_fee(&mut self, currency: Pubkey, deposit_fee: u16) -> Result<()> {
         require_gte!(MAX_FEE, deposit_fee, SolvError::InvalidFeeRatio);
-        let index = self.deposit_currencies.iter().position(|token| token.mint.eq(&currency)).ok_or(SolvError::CurrencyNotFound)?;
+        let index = self.currency_index(&currency)?;
         self.deposit_currencies[index].deposit_fee = deposit_fee;
         self.update()
     }
 
+    pub fn set_max_total_shares(&mut self, max_total_shares: u64) -> Result<()> {
+        self.max_total_shares = max_total_shares;
+        self.update()
+    }
+
+    pub fn set_max_user_shares(&mut self, max_user_shares: u64) -> Result<()> {
+        self.max_user_shares = max_user_shares;
+        self.update()
+    }
+
+    pub fn set_deposit_cap(&mut self, currency: Pubkey, deposit_cap: u64) -> Result<()> {
+        let index = self.currency_index(&currency)?;
+        self.currency_configs[index].deposit_cap = deposit_cap;
+        self.update()
+    }
+
+    /// Track cumulative deposits of a currency and enforce its deposit cap if one is set
+    pub fn record_deposit(&mut self, currency: &Pubkey, amount: u64) -> Result<()> {
+        let index = self.currency_index(currency)?;
+        let config = &mut self.currency_configs[index];
+        config.total_deposited = config.total_deposited.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        if config.deposit_cap > 0 {
+            require_gte!(config.deposit_cap, config.total_deposited, SolvError::CurrencyCapExceeded);
+        }
+        Ok(())
+    }
+
+    /// Enforce the total share cap against the target mint supply after minting
+    pub fn check_total_shares(&self, supply: u64, minted: u64) -> Result<()> {
+        if self.max_total_shares > 0 {
+            let total_shares = supply.checked_add(minted).ok_or(ProgramError::ArithmeticOverflow)?;
+            require_gte!(self.max_total_shares, total_shares, SolvError::VaultCapExceeded);
+        }
+        Ok(())
+    }
+
+    pub fn set_cancel_delay(&mut self, cancel_delay: u32) -> Result<()> {
+        self.cancel_delay = cancel_delay;
+        self.update()
+    }
+
+    pub fn set_withdraw_expiry(&mut self, withdraw_request_ttl: u32, expiry_policy: ExpiryPolicy) -> Result<()> {
+        self.withdraw_request_ttl = withdraw_request_ttl;
+        self.expiry_policy = expiry_policy;
+        self.update()
+    }
+
+    /// Assign the next sequence number to a new withdraw request
+    pub fn next_withdraw_sequence(&mut self) -> Result<u64> {
+        let sequence = self.withdraw_request_count;
+        self.withdraw_request_count = sequence.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(sequence)
+    }
+
+    /// Open a withdraw request for burned shares under the next sequence number, priced at the
+    /// current NAV or, in epoch mode, left to be priced when its redemption epoch is settled
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_withdraw_request(
+        &mut self,
+        withdraw_request: &mut WithdrawRequest,
+        vault: Pubkey,
+        user: Pubkey,
+        withdraw_token_account: Pubkey,
+        withdraw_token: Pubkey,
+        token: Pubkey,
+        shares: u64,
+    ) -> Result<()> {
+        self.check_nav_fresh()?;
+        let sequence = self.next_withdraw_sequence()?;
+
+        let epoch = self.request_epoch();
+        let withdraw_amount = match epoch {
+            Some(_) => 0,
+            None => self.withdrawal_from_shares(&withdraw_token, shares)?,
+        };
+
+        withdraw_request.initialize(
+            user,
+            vault,
+            withdraw_token_account,
+            withdraw_token,
+            withdraw_amount,
+            token,
+            shares,
+            sequence,
+            self.nav,
+            self.withdraw_request_ttl,
+            epoch,
+        )
+    }
+
+    /// Owner of the token account receiving the unfilled shares of an expired withdraw request
+    pub fn expired_shares_recipient(&self, user: Pubkey) -> Pubkey {
+        match self.expiry_policy {
+            ExpiryPolicy::RestoreShares => user,
+            ExpiryPolicy::Treasurer => self.treasurer,
+        }
+    }
+
+    pub fn set_instant_redeem(&mut self, instant_redeem_fee: u16, instant_redeem_cap: u64, instant_redeem_window: u32) -> Result<()> {
+        require_gte!(MAX_FEE, instant_redeem_fee, SolvError::InvalidFeeRatio);
+        // A zero window would start a new window, and reset the cap, on every redemption
+        if instant_redeem_cap > 0 {
+            require_gt!(instant_redeem_window, 0, SolvError::InvalidInstantRedeemWindow);
+        }
+        self.instant_redeem_fee = instant_redeem_fee;
+        self.instant_redeem_cap = instant_redeem_cap;
+        self.instant_redeem_window = instant_redeem_window;
+        self.update()
+    }
+
+    /// Track shares redeemed instantly within the cap of the current window, starting a new window
+    /// once the previous one has elapsed. Returns false, recording nothing, if the cap would be exceeded.
+    pub fn record_instant_redeem(&mut self, shares: u64) -> Result<bool> {
+        let now = Clock::get()?.unix_timestamp;
+        let window_end = self.instant_redeem_window_start
+            .checked_add(self.instant_redeem_window.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if now >= window_end {
+            self.instant_redeem_window_start = now;
+            self.instant_redeemed = 0;
+        }
+
+        let instant_redeemed = self.instant_redeemed.checked_add(shares).ok_or(ProgramError::ArithmeticOverflow)?;
+        if instant_redeemed > self.instant_redeem_cap {
+            return Ok(false);
+        }
+        self.instant_redeemed = instant_redeemed;
+        Ok(true)
+    }
+
+    pub fn set_instant_redeem_reserve(&mut self, currency: Pubkey, instant_redeem_reserve: u64) -> Result<()> {
+        let index = self.currency_index(&currency)?;
+        self.currency_configs[index].instant_redeem_reserve = instant_redeem_reserve;
+        self.update()
+    }
+
+    /// Liquidity of a currency available to instant redemptions from the vault balance,
+    /// after the reserve held back for pending withdraw requests
+    pub fn instant_redeem_liquidity(&self, currency: &Pubkey, balance: u64) -> Result<u64> {
+        Ok(balance.saturating_sub(self.currency_config(currency)?.instant_redeem_reserve))
+    }
+
+    pub fn set_address_book_delay(&mut self, address_book_delay: u32) -> Result<()> {
+        self.address_book_delay = address_book_delay;
+        self.update()
+    }
+
     pub fn set_withdraw_fee(&mut self, withdraw_fee: u16) -> Result<()> {
         require_gte!(MAX_FEE, withdraw_fee, SolvError::InvalidFeeRatio);
         self.withdraw_fee = withdraw_fee;
@@ -91,20 +435,190 @@ impl Vault {
     }
 
     pub fn set_verifier(&mut self, verifier: [u8; 64]) -> Result<()> {
+        self.replace_verifier(verifier, VerifierType::Secp256k1Ecdsa)
+    }
+
+    /// Approve withdrawals with the ECDSA verifier through the secp256k1_recover syscall
+    pub fn set_recover_verifier(&mut self, verifier: [u8; 64]) -> Result<()> {
+        self.replace_verifier(verifier, VerifierType::Secp256k1Recover)
+    }
+
+    /// Approve withdrawals with Ed25519 signatures checked by the Ed25519 program
+    pub fn set_ed25519_verifier(&mut self, verifier: Pubkey) -> Result<()> {
+        require_keys_neq!(verifier, Pubkey::default(), SolvError::InvalidAddress);
+        let mut key = [0u8; 64];
+        key[..32].copy_from_slice(verifier.as_ref());
+        self.replace_verifier(key, VerifierType::Ed25519)
+    }
+
+    /// Approve withdrawals with BIP-340 Schnorr signatures by an x-only key
+    pub fn set_schnorr_verifier(&mut self, verifier: [u8; 32]) -> Result<()> {
+        require!(verifier.ne(&[0u8; 32]), SolvError::InvalidAddress);
+        let mut key = [0u8; 64];
+        key[..32].copy_from_slice(&verifier);
+        self.replace_verifier(key, VerifierType::Schnorr)
+    }
+
+    /// Approve withdrawals with EIP-712 signatures from an Ethereum address
+    pub fn set_eip712_verifier(&mut self, address: [u8; 20]) -> Result<()> {
+        require!(address.ne(&[0u8; 20]), SolvError::InvalidAddress);
+        let mut key = [0u8; 64];
+        key[..20].copy_from_slice(&address);
+        self.replace_verifier(key, VerifierType::Eip712)
+    }
+
+    /// Swap the verifier immediately, dropping any staged rotation and the previous verifier
+    fn replace_verifier(&mut self, verifier: [u8; 64], verifier_type: VerifierType) -> Result<()> {
         self.verifier = verifier;
+        self.verifier_type = verifier_type;
+        self.pending_verifier = [0u8; 64];
+        self.verifier_activation = 0;
+        self.previous_verifier = [0u8; 64];
+        self.previous_verifier_expiry = 0;
         self.update()
     }
 
+    /// Stage a verifier, in the key format of its type, to replace the current one at `activates_at`
+    pub fn stage_verifier(&mut self, vault: Pubkey, verifier: [u8; 64], verifier_type: VerifierType, activates_at: i64) -> Result<()> {
+        verifier_type.check_key(&verifier)?;
+        require_gte!(activates_at, Clock::get()?.unix_timestamp, SolvError::InvalidVerifierActivation);
+        self.pending_verifier = verifier;
+        self.pending_verifier_type = verifier_type;
+        self.verifier_activation = activates_at;
+
+        emit!(VerifierStaged {
+            vault,
+            verifier,
+            verifier_type,
+            activates_at,
+        });
+
+        self.rotate_verifier(vault)?;
+        self.update()
+    }
+
+    pub fn set_verifier_overlap(&mut self, verifier_overlap: u32) -> Result<()> {
+        self.verifier_overlap = verifier_overlap;
+        self.update()
+    }
+
+    /// Activate the staged verifier once its activation time has passed, keeping the replaced
+    /// verifier for the overlap window, and retire the previous verifier once the window ends
+    pub fn rotate_verifier(&mut self, vault: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        if self.verifier_activation.ne(&0) && now >= self.verifier_activation {
+            // A previous verifier still in its overlap window is replaced
+            self.retire_previous_verifier(vault);
+            self.previous_verifier = self.verifier;
+            self.previous_verifier_type = self.verifier_type;
+            self.previous_verifier_expiry = self
+                .verifier_activation
+                .checked_add(self.verifier_overlap.into())
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            self.verifier = self.pending_verifier;
+            self.verifier_type = self.pending_verifier_type;
+            self.pending_verifier = [0u8; 64];
+            self.verifier_activation = 0;
+
+            emit!(VerifierActivated {
+                vault,
+                verifier: self.verifier,
+                verifier_type: self.verifier_type,
+                previous_verifier_expiry: self.previous_verifier_expiry,
+            });
+        }
+
+        if self.previous_verifier_expiry.ne(&0) && now >= self.previous_verifier_expiry {
+            self.retire_previous_verifier(vault);
+        }
+        Ok(())
+    }
+
+    fn retire_previous_verifier(&mut self, vault: Pubkey) {
+        if self.previous_verifier_expiry.eq(&0) {
+            return;
+        }
+        emit!(VerifierRetired {
+            vault,
+            verifier: self.previous_verifier,
+            verifier_type: self.previous_verifier_type,
+        });
+        self.previous_verifier = [0u8; 64];
+        self.previous_verifier_expiry = 0;
+    }
+
+    /// Changing the chain ID invalidates all signatures made under the previous one
+    pub fn set_chain_id(&mut self, chain_id: u64) -> Result<()> {
+        require_gt!(chain_id, 0, SolvError::InvalidChainId);
+        self.chain_id = chain_id;
+        self.update()
+    }
+
+    /// Verify the approval of a withdraw message. Once the verifier set has a threshold, that many
+    /// distinct members must sign, otherwise the vault verifier signs alone as signer 0.
+    pub fn verify_withdraw_signatures(
+        &self,
+        verifier_set: Option<&VerifierSet>,
+        instructions: Option<&AccountInfo>,
+        signatures: &[VerifierSignature],
+        message: impl Fn(MessageVersion) -> [u8; 32],
+    ) -> Result<()> {
+        let Some(verifier_set) = verifier_set.filter(|verifier_set| verifier_set.threshold > 0) else {
+            let [VerifierSignature { signer: 0, signature }] = signatures else {
+                return Err(SolvError::VerifierThresholdNotMet.into());
+            };
+            let result = self.verify_withdraw_signature(&self.verifier, self.verifier_type, instructions, *signature, &message);
+            // The previous verifier is accepted until the end of the overlap window
+            if result.is_err() && self.previous_verifier_expiry.ne(&0) && Clock::get()?.unix_timestamp < self.previous_verifier_expiry {
+                return self.verify_withdraw_signature(&self.previous_verifier, self.previous_verifier_type, instructions, *signature, &message);
+            }
+            return result;
+        };
+
+        require_gte!(signatures.len(), usize::from(verifier_set.threshold), SolvError::VerifierThresholdNotMet);
+        let mut signed = [false; 10];
+        for VerifierSignature { signer, signature } in signatures {
+            let (verifier, verifier_type) = verifier_set.verifier(*signer)?;
+            require!(!signed[usize::from(*signer)], SolvError::DuplicateVerifierSignature);
+            signed[usize::from(*signer)] = true;
+            self.verify_withdraw_signature(verifier, verifier_type, instructions, *signature, &message)?;
+        }
+        Ok(())
+    }
+
+    /// Verify a signature of `verifier` over a withdraw message in the format of its type
+    fn verify_withdraw_signature(
+        &self,
+        verifier: &[u8; 64],
+        verifier_type: VerifierType,
+        instructions: Option<&AccountInfo>,
+        signature: [u8; 64],
+        message: &impl Fn(MessageVersion) -> [u8; 32],
+    ) -> Result<()> {
+        if verifier_type.eq(&VerifierType::Eip712) {
+            return WithdrawRequest::verify_eip712_signature(&message(MessageVersion::Eip712), &signature, &verifier[..20]);
+        }
+        let message = message(MessageVersion::V2);
+        match verifier_type {
+            VerifierType::Secp256k1Recover => WithdrawRequest::recover_signature(&message, &signature, verifier),
+            VerifierType::Ed25519 => WithdrawRequest::verify_ed25519_signature(instructions, &message, &signature, &verifier[..32]),
+            VerifierType::Schnorr => WithdrawRequest::verify_schnorr_signature(&message, &signature, &verifier[..32]),
+            _ => WithdrawRequest::verify_signature(&message, Secp256k1EcdsaSignature(signature), *verifier),
+        }
+    }
+
     pub fn set_treasurer(&mut self, treasurer: Pubkey) -> Result<()> {
         self.treasurer = treasurer;
         self.update()
     }
 
-    pub fn add_currency(&mut self, mint: Pubkey, deposit_fee: u16) -> Result<()> {
+    pub fn add_currency(&mut self, mint: Pubkey, decimals: u8, deposit_fee: u16, rate: u64) -> Result<()> {
         // Ensure we are not trying to add a null address
         if mint.eq(&Pubkey::default()) {
             return Err(SolvError::InvalidAddress.into());
         }
+        require_gt!(rate, 0, SolvError::InvalidCurrencyRate);
         // Find the first empty slot (Pubkey::default())
         if let Some(empty_index) = self
             .deposit_currencies
@@ -118,6 +632,7 @@ impl Vault {
 
             // Add the currency to the first empty slot
             self.deposit_currencies[empty_index] = WhitelistedToken { mint, deposit_fee };
+            self.currency_configs[empty_index] = CurrencyConfig { decimals, rate, ..Default::default() };
 
             self.update()
         } else {
@@ -140,9 +655,11 @@ impl Vault {
             // Shift all elements after the found index up by one position
             for i in index..self.deposit_currencies.len() - 1 {
                 self.deposit_currencies[i] = self.deposit_currencies[i + 1];
+                self.currency_configs[i] = self.currency_configs[i + 1];
             }
             // Set the last element to default (empty)
             self.deposit_currencies[self.deposit_currencies.len() - 1] = WhitelistedToken::default();
+            self.currency_configs[self.currency_configs.len() - 1] = CurrencyConfig::default();
             
             self.update()
         } else {
@@ -152,23 +669,104 @@ impl Vault {
     }
 
     pub fn set_nav(&mut self, nav: u64) -> Result<()> {
+        // Even a full max NAV change cannot take the NAV below the initial floor
+        require_gte!(nav, ONE_BITCOIN, SolvError::InvalidNAVValue);
+        self.check_max_change(self.nav, nav, SolvError::InvalidNAVValue)?;
+        self.nav = nav;
+        self.nav_updated = Clock::get()?.unix_timestamp;
+        self.update()
+    }
+
+    pub fn set_withdraw_nav_tolerance(&mut self, withdraw_nav_tolerance: u16) -> Result<()> {
+        require_gte!(MAX_FEE, withdraw_nav_tolerance, SolvError::InvalidMaxNavChange);
+        self.withdraw_nav_tolerance = withdraw_nav_tolerance;
+        self.update()
+    }
+
+    /// Set the maximum NAV change per update, which must allow some change without exceeding 100%
+    pub fn set_max_nav_change(&mut self, max_nav_change: u16) -> Result<()> {
+        require_gt!(max_nav_change, 0, SolvError::InvalidMaxNavChange);
+        require_gte!(MAX_FEE, max_nav_change, SolvError::InvalidMaxNavChange);
+        self.max_nav_change = max_nav_change;
+        self.update()
+    }
+
+    pub fn set_max_nav_age(&mut self, max_nav_age: u32) -> Result<()> {
+        self.max_nav_age = max_nav_age;
+        self.update()
+    }
+
+    /// Ensure the NAV has been updated within the maximum NAV age, if one is set
+    pub fn check_nav_fresh(&self) -> Result<()> {
+        if self.max_nav_age > 0 {
+            let nav_age = Clock::get()?.unix_timestamp.saturating_sub(self.nav_updated);
+            require_gte!(i64::from(self.max_nav_age), nav_age, SolvError::StaleNAV);
+        }
+        Ok(())
+    }
+
+    pub fn set_currency_rate(&mut self, currency: Pubkey, rate: u64) -> Result<()> {
+        require_gt!(rate, 0, SolvError::InvalidCurrencyRate);
+        let index = self.currency_index(&currency)?;
+        self.check_max_change(self.currency_configs[index].rate, rate, SolvError::InvalidCurrencyRate)?;
+        self.currency_configs[index].rate = rate;
+        self.update()
+    }
 
-        // Check nav growth/ does not exceed 0.05%
-        let nav_diff: u64 = u64::try_from(u128::from(self.nav)
-            .checked_mul(5 as u128)
+    /// Check growth/decline of an oracle value does not exceed the maximum NAV change
+    fn check_max_change(&self, current: u64, value: u64, error: SolvError) -> Result<()> {
+        let diff: u64 = u64::try_from(u128::from(current)
+            .checked_mul(self.max_nav_change.into())
             .ok_or(ProgramError::ArithmeticOverflow)?
             .checked_div(MAX_FEE.into())
             .ok_or(ProgramError::ArithmeticOverflow)?)
             .map_err(|_| ProgramError::ArithmeticOverflow)?;
         
-        let max_nav = self.nav.checked_add(nav_diff).ok_or(ProgramError::ArithmeticOverflow)?;
-        let min_nav = self.nav.checked_sub(nav_diff).ok_or(ProgramError::ArithmeticOverflow)?;
-        require_gte!(max_nav, nav, SolvError::InvalidNAVValue);
-        require_gte!(nav, min_nav, SolvError::InvalidNAVValue);
-        self.nav = nav;
+        let max_value = current.checked_add(diff).ok_or(ProgramError::ArithmeticOverflow)?;
+        let min_value = current.checked_sub(diff).ok_or(ProgramError::ArithmeticOverflow)?;
+        require_gte!(max_value, value, error);
+        require_gte!(value, min_value, error);
+        Ok(())
+    }
+
+    pub fn set_settlement_policy(&mut self, settlement_policy: SettlementPolicy) -> Result<()> {
+        self.settlement_policy = settlement_policy;
+        self.update()
+    }
+
+    pub fn set_epoch_mode(&mut self, epoch_mode: bool) -> Result<()> {
+        self.epoch_mode = epoch_mode;
         self.update()
     }
 
+    /// Redemption epoch a new withdraw request joins, None if it is priced immediately
+    pub fn request_epoch(&self) -> Option<u64> {
+        self.epoch_mode.then_some(self.current_epoch)
+    }
+
+    /// Close the current redemption epoch at a settlement NAV bounded like NAV updates,
+    /// returning the closed epoch
+    pub fn close_redemption_epoch(&mut self, nav: u64) -> Result<u64> {
+        require_gte!(nav, ONE_BITCOIN, SolvError::InvalidNAVValue);
+        self.check_max_change(self.nav, nav, SolvError::InvalidNAVValue)?;
+        let epoch = self.current_epoch;
+        self.current_epoch = epoch.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.update()?;
+        Ok(epoch)
+    }
+
+    /// Price a withdraw request awaiting settlement at the NAV of its closed redemption epoch
+    pub fn settle_withdraw_request(&self, withdraw_request: &mut WithdrawRequest, redemption_epoch: Option<&RedemptionEpoch>) -> Result<()> {
+        let Some(epoch) = withdraw_request.epoch else {
+            return Ok(());
+        };
+        let redemption_epoch = redemption_epoch.ok_or(SolvError::EpochNotSettled)?;
+        require_eq!(redemption_epoch.epoch, epoch, SolvError::EpochNotSettled);
+
+        let withdraw_amount = self.withdrawal_from_shares_at(&withdraw_request.withdraw_token, withdraw_request.shares, redemption_epoch.nav)?;
+        withdraw_request.settle(redemption_epoch.vault, withdraw_amount, redemption_epoch.nav)
+    }
+
     pub fn set_oracle_manager(&mut self, manager: Pubkey) -> Result<()> {
         self.oracle_manager = manager;
         self.update()
@@ -186,33 +784,116 @@ impl Vault {
         Ok((amount, fee))
     }
 
+    fn currency_index(&self, currency: &Pubkey) -> Result<usize> {
+        self.deposit_currencies.iter().position(|token| token.mint.eq(currency)).ok_or(SolvError::CurrencyNotFound.into())
+    }
+
+    fn currency_config(&self, currency: &Pubkey) -> Result<&CurrencyConfig> {
+        Ok(&self.currency_configs[self.currency_index(currency)?])
+    }
+
+    /// Validate a fill of a withdraw request and split it into the payout and withdraw fee.
+    /// Returns (amount, fee, shortfall, filled) where shortfall is the amount withheld when the
+    /// NAV has fallen below the request NAV and filled is true once the request is fully filled.
+    pub fn fill_withdraw_request(&self, withdraw_request: &mut WithdrawRequest, amount: u64) -> Result<(u64, u64, u64, bool)> {
+        // Signatures are no longer accepted once the request has expired
+        require!(!withdraw_request.is_expired()?, SolvError::WithdrawRequestExpired);
+
+        // Check (1 + tolerance)*nav >= nav of withdraw request. Under the lower NAV policy the
+        // payout is already capped at the current NAV, so a larger drop is settled, not rejected.
+        if self.settlement_policy.eq(&SettlementPolicy::RequestNav) {
+            let nav_diff: u64 = u64::try_from(u128::from(self.nav)
+                .checked_mul(self.withdraw_nav_tolerance.into())
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_div(MAX_FEE.into())
+                .ok_or(ProgramError::ArithmeticOverflow)?)
+                .map_err(|_| ProgramError::ArithmeticOverflow)?;
+            let max_nav = self.nav.checked_add(nav_diff).ok_or(ProgramError::ArithmeticOverflow)?;
+            require_gte!(max_nav, withdraw_request.nav, SolvError::NAVExceeded);
+        }
+
+        let filled = withdraw_request.fill(amount)?;
+
+        // Pay out at the lower of the request NAV and the current NAV if the settlement policy requires it
+        let payout = self.settlement_amount(withdraw_request.nav, amount)?;
+        let shortfall = amount.checked_sub(payout).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // Get withdraw amount and withdraw fee
+        let (amount, fee) = Self::calculate_fee(payout, self.withdraw_fee)?;
+        msg!("Withdraw amount: {}, Fee: {}, Shortfall: {}", amount, fee, shortfall);
+
+        Ok((amount, fee, shortfall, filled))
+    }
+
+    /// Amount paid for a fill priced at the request NAV under the settlement policy
+    /// amount * min(request_nav, nav) / request_nav = payout
+    fn settlement_amount(&self, request_nav: u64, amount: u64) -> Result<u64> {
+        if self.settlement_policy.eq(&SettlementPolicy::RequestNav) || self.nav >= request_nav {
+            return Ok(amount);
+        }
+        u128::from(amount)
+            .checked_mul(self.nav.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(request_nav.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .try_into()
+            .map_err(|_| ProgramError::ArithmeticOverflow.into())
+    }
+
     pub fn deposit_fee(&self, currency: &Pubkey) -> Result<u16> {
-        let index = self.deposit_currencies.iter().position(|token| token.mint.eq(currency)).ok_or(SolvError::CurrencyNotFound)?;
-        Ok(self.deposit_currencies[index].deposit_fee)
+        Ok(self.deposit_currencies[self.currency_index(currency)?].deposit_fee)
     }
 
-    /// Calculate shares to mint from a deposit amount
-    /// deposit_amount * ONE_BITCOIN / nav = shares
-    pub fn shares_from_deposit(&self, deposit_amount: u64) -> Result<u64> {
-        u128::from(deposit_amount)
-            .checked_mul(ONE_BITCOIN.into())
+    /// Calculate shares to mint from a deposit amount at the currency rate, normalized by the
+    /// decimals of the deposit currency and the target mint. Rounds down in favor of the vault.
+    /// deposit_amount * rate * 10^target_decimals / (nav * 10^currency_decimals) = shares
+    pub fn shares_from_deposit(&self, currency: &Pubkey, deposit_amount: u64) -> Result<u64> {
+        let currency = self.currency_config(currency)?;
+        let numerator = u128::from(deposit_amount)
+            .checked_mul(currency.rate.into())
             .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(self.nav.into())
+            .checked_mul(Self::decimals_factor(self.decimals)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let denominator = u128::from(self.nav)
+            .checked_mul(Self::decimals_factor(currency.decimals)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        numerator
+            .checked_div(denominator)
             .ok_or(ProgramError::ArithmeticOverflow)?
             .try_into()
             .map_err(|_| ProgramError::ArithmeticOverflow.into())
     }
 
-    /// Calculate withdrawal amount from shares to burn
-    /// shares * nav / ONE_BITCOIN = withdrawal_amount
-    pub fn withdrawal_from_shares(&self, shares: u64) -> Result<u64> {
-        require_gte!(self.nav, ONE_BITCOIN, SolvError::InvalidNAVValue);
-        u128::from(shares)
-            .checked_mul(self.nav.into())
+    /// Calculate withdrawal amount from shares to burn at the currency rate, normalized by the
+    /// decimals of the target mint and the withdraw currency. Rounds down in favor of the vault.
+    /// shares * nav * 10^currency_decimals / (rate * 10^target_decimals) = withdrawal_amount
+    pub fn withdrawal_from_shares(&self, currency: &Pubkey, shares: u64) -> Result<u64> {
+        self.withdrawal_from_shares_at(currency, shares, self.nav)
+    }
+
+    /// Calculate withdrawal amount from shares at a given NAV, such as the settlement NAV of a redemption epoch
+    pub fn withdrawal_from_shares_at(&self, currency: &Pubkey, shares: u64, nav: u64) -> Result<u64> {
+        require_gte!(nav, ONE_BITCOIN, SolvError::InvalidNAVValue);
+        let currency = self.currency_config(currency)?;
+        let numerator = u128::from(shares)
+            .checked_mul(nav.into())
             .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(ONE_BITCOIN.into())
+            .checked_mul(Self::decimals_factor(currency.decimals)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let denominator = u128::from(currency.rate)
+            .checked_mul(Self::decimals_factor(self.decimals)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        numerator
+            .checked_div(denominator)
             .ok_or(ProgramError::ArithmeticOverflow)?
             .try_into()
             .map_err(|_| ProgramError::ArithmeticOverflow.into())
     }
+
+    #[inline(always)]
+    fn decimals_factor(decimals: u8) -> Result<u128> {
+        10u128
+            .checked_pow(decimals.into())
+            .ok_or(ProgramError::ArithmeticOverflow.into())
+    }
 }
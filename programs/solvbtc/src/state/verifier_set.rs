@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::SolvError, state::VerifierType};
+
+#[account(discriminator = [7])]
+#[derive(InitSpace)]
+pub struct VerifierSet {
+    pub vault: Pubkey,
+    /// Number of distinct members that must approve a withdrawal, 0 if the vault verifier approves alone
+    pub threshold: u8,
+    /// Member keys in the format of their type, empty slots are zeroed
+    pub verifiers: [[u8; 64]; 10],
+    /// Signature scheme of each member, independent of the vault verifier type
+    pub verifier_types: [VerifierType; 10],
+    pub bump: u8,
+}
+
+/// Signature of the verifier set member at index `signer`
+#[derive(Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct VerifierSignature {
+    pub signer: u8,
+    pub signature: [u8; 64],
+}
+
+impl VerifierSet {
+    pub fn add_verifier(&mut self, vault: Pubkey, verifier: [u8; 64], verifier_type: VerifierType, bump: u8) -> Result<()> {
+        // Initialize on first member
+        if self.vault.eq(&Pubkey::default()) {
+            self.vault = vault;
+            self.bump = bump;
+        }
+
+        verifier_type.check_key(&verifier)?;
+        if self.verifiers.contains(&verifier) {
+            return Err(SolvError::VerifierExists.into());
+        }
+
+        let index = self
+            .verifiers
+            .iter()
+            .position(|member| member.eq(&[0u8; 64]))
+            .ok_or(SolvError::VerifierSetFull)?;
+        self.verifiers[index] = verifier;
+        self.verifier_types[index] = verifier_type;
+        Ok(())
+    }
+
+    /// Remove a member, keeping enough members to meet the threshold
+    pub fn remove_verifier(&mut self, verifier: [u8; 64]) -> Result<()> {
+        let index = self
+            .verifiers
+            .iter()
+            .position(|member| member.eq(&verifier))
+            .ok_or(SolvError::VerifierNotFound)?;
+        self.verifiers[index] = [0u8; 64];
+        require_gte!(self.members(), self.threshold, SolvError::InvalidVerifierThreshold);
+        Ok(())
+    }
+
+    pub fn set_threshold(&mut self, threshold: u8) -> Result<()> {
+        require_gte!(self.members(), threshold, SolvError::InvalidVerifierThreshold);
+        self.threshold = threshold;
+        Ok(())
+    }
+
+    pub fn members(&self) -> u8 {
+        self.verifiers.iter().filter(|member| member.ne(&&[0u8; 64])).count() as u8
+    }
+
+    /// Key and type of the member at `signer`
+    pub fn verifier(&self, signer: u8) -> Result<(&[u8; 64], VerifierType)> {
+        let index = usize::from(signer);
+        self.verifiers
+            .get(index)
+            .filter(|member| member.ne(&&[0u8; 64]))
+            .map(|member| (member, self.verifier_types[index]))
+            .ok_or(SolvError::VerifierNotFound.into())
+    }
+}
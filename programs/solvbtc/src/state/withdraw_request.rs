@@ -1,8 +1,143 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        ed25519_program,
+        secp256k1_recover::secp256k1_recover,
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
 use solana_secp256k1::UncompressedPoint;
 use solana_secp256k1_ecdsa::{hash::sha256::Sha256, Secp256k1EcdsaSignature};
 
-use crate::events::WithdrawRequestEvent;
+use crate::{
+    constants::{
+        BIP340_CHALLENGE_TAG, EIP712_DOMAIN_TYPE, EIP712_DOMAIN_VERSION, EIP712_FILL_TYPE, EIP712_REQUEST_TYPE,
+        EIP712_SWITCH_TYPE, SECP256K1_HALF_ORDER, SECP256K1_ORDER, WITHDRAW_MESSAGE_DOMAIN,
+    },
+    errors::SolvError,
+    events::{WithdrawRequestEvent, WithdrawSettleEvent, WithdrawSwitchEvent},
+};
+
+/// Format of the messages signed by the verifier
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MessageVersion {
+    V2 = 2,
+    /// EIP-712 typed data digest signed by an Ethereum account
+    Eip712 = 3,
+}
+
+/// Encode a u64 as a 32-byte big-endian ABI word
+fn eip712_uint(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Scalar modulo the secp256k1 order as little-endian u64 limbs
+type Scalar = [u64; 4];
+
+fn scalar_from_bytes(bytes: &[u8]) -> Scalar {
+    core::array::from_fn(|i| u64::from_be_bytes(bytes[24 - 8 * i..32 - 8 * i].try_into().unwrap()))
+}
+
+fn scalar_to_bytes(scalar: &Scalar) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (i, limb) in scalar.iter().enumerate() {
+        bytes[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+    }
+    bytes
+}
+
+/// a - b, with the borrow out of the top limb
+fn scalar_sub(a: &Scalar, b: &Scalar) -> (Scalar, bool) {
+    let mut difference = [0u64; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (limb, borrow_a) = a[i].overflowing_sub(b[i]);
+        let (limb, borrow_b) = limb.overflowing_sub(borrow.into());
+        difference[i] = limb;
+        borrow = borrow_a || borrow_b;
+    }
+    (difference, borrow)
+}
+
+/// a mod n for any a, as a < 2^256 < 2n
+fn scalar_reduce(a: &Scalar) -> Scalar {
+    match scalar_sub(a, &scalar_from_bytes(&SECP256K1_ORDER)) {
+        (_, true) => *a,
+        (reduced, false) => reduced,
+    }
+}
+
+/// (a + b) mod n for a, b < n
+fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar {
+    let mut sum = [0u64; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (limb, carry_a) = a[i].overflowing_add(b[i]);
+        let (limb, carry_b) = limb.overflowing_add(carry.into());
+        sum[i] = limb;
+        carry = carry_a || carry_b;
+    }
+    match scalar_sub(&sum, &scalar_from_bytes(&SECP256K1_ORDER)) {
+        (reduced, borrow) if carry || !borrow => reduced,
+        _ => sum,
+    }
+}
+
+/// (a * b) mod n for a, b < n, by double-and-add over the bits of b
+fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar {
+    let mut product = [0u64; 4];
+    for bit in (0..256).rev() {
+        product = scalar_add(&product, &product);
+        if (b[bit / 64] >> (bit % 64)) & 1 == 1 {
+            product = scalar_add(&product, a);
+        }
+    }
+    product
+}
+
+/// -a mod n for a < n
+fn scalar_neg(a: &Scalar) -> Scalar {
+    if a.eq(&[0u64; 4]) {
+        return *a;
+    }
+    scalar_sub(&scalar_from_bytes(&SECP256K1_ORDER), a).0
+}
+
+/// Whether Ed25519 program instruction data checks `signature` by `verifier` over `message`,
+/// with the signature, key and message all read from the instruction itself
+fn ed25519_instruction_verifies(data: &[u8], message: &[u8; 32], signature: &[u8; 64], verifier: &[u8]) -> bool {
+    let Some(&count) = data.first() else {
+        return false;
+    };
+    (0..usize::from(count)).any(|i| {
+        // Offsets of the signature, its instruction index, the key, its instruction index, the
+        // message, its size and its instruction index
+        let Some(offsets) = data.get(2 + i * 14..2 + (i + 1) * 14) else {
+            return false;
+        };
+        let offset = |n: usize| usize::from(u16::from_le_bytes([offsets[2 * n], offsets[2 * n + 1]]));
+        if [1, 3, 6].iter().any(|&n| offset(n).ne(&usize::from(u16::MAX))) || offset(5).ne(&32) {
+            return false;
+        }
+        data.get(offset(0)..offset(0) + 64).eq(&Some(&signature[..]))
+            && data.get(offset(2)..offset(2) + 32).eq(&Some(verifier))
+            && data.get(offset(4)..offset(4) + 32).eq(&Some(&message[..]))
+    })
+}
+
+/// Digest of an EIP-712 struct hash under the domain of this program and chain
+fn eip712_digest(chain_id: u64, struct_hash: &[u8; 32]) -> [u8; 32] {
+    let domain_separator = solana_nostd_keccak::hashv(&[
+        solana_nostd_keccak::hash(EIP712_DOMAIN_TYPE).as_ref(),
+        solana_nostd_keccak::hash(WITHDRAW_MESSAGE_DOMAIN).as_ref(),
+        solana_nostd_keccak::hash(EIP712_DOMAIN_VERSION).as_ref(),
+        eip712_uint(chain_id).as_ref(),
+        crate::ID.as_ref(),
+    ]);
+    solana_nostd_keccak::hashv(&[b"\x19\x01", domain_separator.as_ref(), struct_hash.as_ref()])
+}
 
 #[account(discriminator = [3])]
 #[derive(InitSpace)]
@@ -13,8 +148,15 @@ pub struct WithdrawRequest {
     pub withdraw_amount: u64,
     pub token: Pubkey,
     pub shares: u64,
-    pub request_hash: [u8; 32],
+    /// Position of the request in the withdraw queue of the vault
+    pub sequence: u64,
     pub nav: u64,
+    pub created_at: i64,
+    pub filled_amount: u64,
+    /// Time after which the request can no longer be withdrawn, 0 if it never expires
+    pub expires_at: i64,
+    /// Redemption epoch whose settlement NAV prices the request, None once priced
+    pub epoch: Option<u64>,
 }
 
 impl WithdrawRequest {
@@ -22,14 +164,23 @@ impl WithdrawRequest {
     pub fn initialize(
         &mut self,
         user: Pubkey,
+        vault: Pubkey,
         withdraw_token_account: Pubkey,
         withdraw_token: Pubkey,
         withdraw_amount: u64,
         token: Pubkey,
         shares: u64,
-        request_hash: [u8; 32],
+        sequence: u64,
         nav: u64,
+        ttl: u32,
+        epoch: Option<u64>,
     ) -> Result<()> {
+        let created_at = Clock::get()?.unix_timestamp;
+        let expires_at = if ttl > 0 {
+            created_at.checked_add(ttl.into()).ok_or(ProgramError::ArithmeticOverflow)?
+        } else {
+            0
+        };
         *self = WithdrawRequest {
             user,
             withdraw_token_account,
@@ -37,38 +188,293 @@ impl WithdrawRequest {
             withdraw_amount,
             token,
             shares,
-            request_hash,
+            sequence,
             nav,
+            created_at,
+            filled_amount: 0,
+            expires_at,
+            epoch,
         };
 
         // Emit initialize event
         emit!(WithdrawRequestEvent {
             user,
+            vault,
             withdraw_token,
             withdraw_amount,
             token,
             shares,
-            request_hash,
-            nav
+            sequence,
+            nav,
+            epoch,
         });
 
         Ok(())
     }
 
-    pub fn verify_signature(&self, signature: Secp256k1EcdsaSignature, verifier: [u8;64]) -> Result<()> {
+    pub fn verify_signature(message: &[u8; 32], signature: Secp256k1EcdsaSignature, verifier: [u8;64]) -> Result<()> {
         Ok(signature
             .normalize_s()
-            .verify::<Sha256, UncompressedPoint>(&self.hash(), UncompressedPoint(verifier))
+            .verify::<Sha256, UncompressedPoint>(message, UncompressedPoint(verifier))
             .map_err(|_| ProgramError::MissingRequiredSignature)?)
     }
-    
-    pub fn hash(&self) -> [u8;32] {
-        solana_nostd_sha256::hashv(&[
+
+    /// Verify a signature with the secp256k1_recover syscall, at a fraction of the compute cost of
+    /// verify_signature. High-s signatures are normalized first so both accept the same signatures.
+    pub fn recover_signature(message: &[u8; 32], signature: &[u8; 64], verifier: &[u8; 64]) -> Result<()> {
+        let digest = solana_nostd_sha256::hash(message);
+        let mut signature = *signature;
+        if signature[32..].gt(&SECP256K1_HALF_ORDER[..]) {
+            // s = n - s
+            let mut borrow = 0u16;
+            for i in (32..64).rev() {
+                let difference = u16::from(SECP256K1_ORDER[i - 32]).wrapping_sub(u16::from(signature[i])).wrapping_sub(borrow);
+                signature[i] = difference as u8;
+                borrow = difference >> 15;
+            }
+        }
+        for recovery_id in 0..2 {
+            if let Ok(pubkey) = secp256k1_recover(&digest, recovery_id, &signature) {
+                if pubkey.to_bytes().eq(verifier) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(ProgramError::MissingRequiredSignature.into())
+    }
+
+    /// Verify that an Ed25519 program instruction earlier in the transaction checked `signature`
+    /// by `verifier` over the message, as the runtime rejects transactions with invalid ones
+    pub fn verify_ed25519_signature(instructions: Option<&AccountInfo>, message: &[u8; 32], signature: &[u8; 64], verifier: &[u8]) -> Result<()> {
+        let instructions = instructions.ok_or(ProgramError::MissingRequiredSignature)?;
+        let current_index = load_current_index_checked(instructions)?;
+        for index in 0..current_index {
+            let instruction = load_instruction_at_checked(index.into(), instructions)?;
+            if instruction.program_id.eq(&ed25519_program::ID)
+                && ed25519_instruction_verifies(&instruction.data, message, signature, verifier)
+            {
+                return Ok(());
+            }
+        }
+        Err(ProgramError::MissingRequiredSignature.into())
+    }
+
+    /// Verify a BIP-340 Schnorr signature by an x-only key with the secp256k1_recover syscall.
+    /// Recovering with r = P.x, s = -e * P.x and hash -s * P.x yields s * G - e * P, which
+    /// must be the signature point R, with even y and x = r.
+    pub fn verify_schnorr_signature(message: &[u8; 32], signature: &[u8; 64], verifier: &[u8]) -> Result<()> {
+        let order = scalar_from_bytes(&SECP256K1_ORDER);
+        let x = scalar_from_bytes(verifier);
+        let s = scalar_from_bytes(&signature[32..]);
+        // The key is used as the recovery r so it must also be a nonzero scalar
+        if x.eq(&[0u64; 4]) || !scalar_sub(&x, &order).1 || !scalar_sub(&s, &order).1 {
+            return Err(ProgramError::MissingRequiredSignature.into());
+        }
+
+        let tag = solana_nostd_sha256::hash(BIP340_CHALLENGE_TAG);
+        let challenge = solana_nostd_sha256::hashv(&[tag.as_ref(), tag.as_ref(), &signature[..32], verifier, message.as_ref()]);
+        let e = scalar_reduce(&scalar_from_bytes(&challenge));
+
+        let mut recovery_signature = [0u8; 64];
+        recovery_signature[..32].copy_from_slice(verifier);
+        recovery_signature[32..].copy_from_slice(&scalar_to_bytes(&scalar_neg(&scalar_mul(&e, &x))));
+        let hash = scalar_to_bytes(&scalar_neg(&scalar_mul(&s, &x)));
+
+        // x-only keys have an even y, recovery id 0
+        let point = secp256k1_recover(&hash, 0, &recovery_signature)
+            .map_err(|_| ProgramError::MissingRequiredSignature)?
+            .to_bytes();
+        if point[..32].ne(&signature[..32]) || point[63] & 1 == 1 {
+            return Err(ProgramError::MissingRequiredSignature.into());
+        }
+        Ok(())
+    }
+
+    /// Recover the Ethereum address that signed an EIP-712 digest and check it against the verifier.
+    /// High-s signatures are rejected so a signature has a single valid encoding.
+    pub fn verify_eip712_signature(digest: &[u8; 32], signature: &[u8; 64], address: &[u8]) -> Result<()> {
+        if signature[32..].gt(&SECP256K1_HALF_ORDER[..]) {
+            return Err(ProgramError::MissingRequiredSignature.into());
+        }
+        for recovery_id in 0..2 {
+            if let Ok(pubkey) = secp256k1_recover(digest, recovery_id, signature) {
+                if solana_nostd_keccak::hash(&pubkey.to_bytes())[12..].eq(address) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(ProgramError::MissingRequiredSignature.into())
+    }
+
+    /// EIP-712 struct hash of the request
+    fn eip712_hash(&self, vault: &Pubkey) -> [u8;32] {
+        solana_nostd_keccak::hashv(&[
+            solana_nostd_keccak::hash(EIP712_REQUEST_TYPE).as_ref(),
+            vault.as_ref(),
             self.user.as_ref(),
             self.withdraw_token.as_ref(),
-            self.request_hash.as_ref(),
-            self.shares.to_le_bytes().as_ref(),
-            self.nav.to_le_bytes().as_ref()
+            self.withdraw_token_account.as_ref(),
+            self.token.as_ref(),
+            eip712_uint(self.sequence).as_ref(),
+            eip712_uint(self.shares).as_ref(),
+            eip712_uint(self.nav).as_ref(),
+            eip712_uint(self.withdraw_amount).as_ref()
+        ])
+    }
+    
+    /// Message signed by the verifier to approve the request. The V2 format is domain separated
+    /// by program, chain ID and vault and covers the full payout of the request.
+    pub fn hash(&self, vault: &Pubkey, chain_id: u64, version: MessageVersion) -> [u8;32] {
+        match version {
+            MessageVersion::V2 => solana_nostd_sha256::hashv(&[
+                WITHDRAW_MESSAGE_DOMAIN,
+                &[MessageVersion::V2 as u8],
+                chain_id.to_le_bytes().as_ref(),
+                crate::ID.as_ref(),
+                vault.as_ref(),
+                self.user.as_ref(),
+                self.withdraw_token.as_ref(),
+                self.withdraw_token_account.as_ref(),
+                self.token.as_ref(),
+                self.sequence.to_le_bytes().as_ref(),
+                self.shares.to_le_bytes().as_ref(),
+                self.nav.to_le_bytes().as_ref(),
+                self.withdraw_amount.to_le_bytes().as_ref()
+            ]),
+            MessageVersion::Eip712 => eip712_digest(chain_id, &self.eip712_hash(vault)),
+        }
+    }
+
+    /// Message signed by the verifier to approve a fill of `amount` on top of the amount
+    /// already filled, so a fill signature cannot be replayed
+    pub fn fill_hash(&self, vault: &Pubkey, chain_id: u64, version: MessageVersion, amount: u64) -> [u8;32] {
+        if version.eq(&MessageVersion::Eip712) {
+            return eip712_digest(chain_id, &solana_nostd_keccak::hashv(&[
+                solana_nostd_keccak::hash(EIP712_FILL_TYPE).as_ref(),
+                self.eip712_hash(vault).as_ref(),
+                eip712_uint(self.filled_amount).as_ref(),
+                eip712_uint(amount).as_ref()
+            ]));
+        }
+        solana_nostd_sha256::hashv(&[
+            self.hash(vault, chain_id, version).as_ref(),
+            self.filled_amount.to_le_bytes().as_ref(),
+            amount.to_le_bytes().as_ref()
         ])
     }
+
+    /// Ensure the request was opened by the user in the given withdraw currency
+    pub fn check_owner(&self, user: &Pubkey, withdraw_token: &Pubkey) -> Result<()> {
+        require_keys_eq!(self.user, *user, SolvError::InvalidAddress);
+        require_keys_eq!(self.withdraw_token, *withdraw_token, SolvError::InvalidAddress);
+        Ok(())
+    }
+
+    /// Message signed by the verifier to approve paying out the unfilled amount of the request
+    /// in another currency to the given token account
+    pub fn switch_hash(&self, vault: &Pubkey, chain_id: u64, version: MessageVersion, withdraw_token: &Pubkey, withdraw_token_account: &Pubkey) -> [u8;32] {
+        if version.eq(&MessageVersion::Eip712) {
+            return eip712_digest(chain_id, &solana_nostd_keccak::hashv(&[
+                solana_nostd_keccak::hash(EIP712_SWITCH_TYPE).as_ref(),
+                self.eip712_hash(vault).as_ref(),
+                eip712_uint(self.filled_amount).as_ref(),
+                withdraw_token.as_ref(),
+                withdraw_token_account.as_ref()
+            ]));
+        }
+        solana_nostd_sha256::hashv(&[
+            self.hash(vault, chain_id, version).as_ref(),
+            self.filled_amount.to_le_bytes().as_ref(),
+            withdraw_token.as_ref(),
+            withdraw_token_account.as_ref()
+        ])
+    }
+
+    /// Create a request for the unfilled shares paid out in another currency under a new sequence,
+    /// keeping the NAV, redemption epoch and lifetime of this request
+    pub fn switch_currency(
+        &self,
+        vault: Pubkey,
+        withdraw_token_account: Pubkey,
+        withdraw_token: Pubkey,
+        withdraw_amount: u64,
+        sequence: u64,
+    ) -> Result<WithdrawRequest> {
+        let shares = self.unfilled_shares()?;
+
+        emit!(WithdrawSwitchEvent {
+            user: self.user,
+            vault,
+            previous_sequence: self.sequence,
+            previous_withdraw_token: self.withdraw_token,
+            sequence,
+            withdraw_token,
+            withdraw_amount,
+            shares,
+        });
+
+        Ok(WithdrawRequest {
+            user: self.user,
+            withdraw_token_account,
+            withdraw_token,
+            withdraw_amount,
+            token: self.token,
+            shares,
+            sequence,
+            nav: self.nav,
+            created_at: self.created_at,
+            filled_amount: 0,
+            expires_at: self.expires_at,
+            epoch: self.epoch,
+        })
+    }
+
+    /// Record the withdraw amount and NAV of a request settled at its redemption epoch
+    pub fn settle(&mut self, vault: Pubkey, withdraw_amount: u64, nav: u64) -> Result<()> {
+        let epoch = self.epoch.take().ok_or(SolvError::EpochNotSettled)?;
+        self.withdraw_amount = withdraw_amount;
+        self.nav = nav;
+
+        emit!(WithdrawSettleEvent {
+            user: self.user,
+            vault,
+            withdraw_token: self.withdraw_token,
+            withdraw_amount,
+            sequence: self.sequence,
+            epoch,
+            nav,
+        });
+
+        Ok(())
+    }
+
+    pub fn is_expired(&self) -> Result<bool> {
+        Ok(self.expires_at.ne(&0) && Clock::get()?.unix_timestamp > self.expires_at)
+    }
+
+    pub fn remaining_amount(&self) -> Result<u64> {
+        Ok(self.withdraw_amount.checked_sub(self.filled_amount).ok_or(ProgramError::ArithmeticOverflow)?)
+    }
+
+    /// Record a fill of `amount`, returning true once the request is fully filled
+    pub fn fill(&mut self, amount: u64) -> Result<bool> {
+        require_gt!(amount, 0, SolvError::InvalidFillAmount);
+        require_gte!(self.remaining_amount()?, amount, SolvError::InvalidFillAmount);
+        self.filled_amount = self.filled_amount.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(self.filled_amount.eq(&self.withdraw_amount))
+    }
+
+    /// Shares not yet paid out, rounded down in favor of the vault
+    pub fn unfilled_shares(&self) -> Result<u64> {
+        if self.filled_amount.eq(&0) {
+            return Ok(self.shares);
+        }
+        u128::from(self.shares)
+            .checked_mul(self.remaining_amount()?.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(self.withdraw_amount.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .try_into()
+            .map_err(|_| ProgramError::ArithmeticOverflow.into())
+    }
 }
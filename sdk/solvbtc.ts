@@ -1,6 +1,7 @@
-import { secp256k1 } from "@noble/curves/secp256k1";
+import { schnorr, secp256k1 } from "@noble/curves/secp256k1";
 import { sha256 } from "@noble/hashes/sha2";
-import { Keypair, PublicKey } from "@solana/web3.js";
+import { keccak_256 } from "@noble/hashes/sha3";
+import { PublicKey } from "@solana/web3.js";
 import BN from "bn.js";
 
 export const SOLVBTC_PROGRAM_ID = new PublicKey("DF4xtEw8N5WfJxmPTtgwg6DcaivVdrUqGpgz3mvhkb2i");
@@ -14,13 +15,39 @@ export const VAULT_SIGNER_SEED = new TextEncoder().encode("vault");
 export const MINTER_MANAGER_SEED = new TextEncoder().encode("minter_manager");
 export const POOL_SIGNER_SEED = new TextEncoder().encode("ccip_tokenpool_signer");
 export const WITHDRAW_REQUEST_SEED = new TextEncoder().encode("withdraw_request");
+export const USER_DEPOSIT_SEED = new TextEncoder().encode("user_deposit");
+export const REDEMPTION_EPOCH_SEED = new TextEncoder().encode("redemption_epoch");
+export const ADDRESS_BOOK_SEED = new TextEncoder().encode("address_book");
+export const VERIFIER_SET_SEED = new TextEncoder().encode("verifier_set");
 
 export const ONE_BITCOIN = new BN(100_000_000)
 
+export const WITHDRAW_MESSAGE_DOMAIN = new TextEncoder().encode("SolvBTC Withdraw");
+export const WITHDRAW_MESSAGE_VERSION = 2
+
+export const EIP712_DOMAIN_VERSION = new TextEncoder().encode("2");
+export const EIP712_DOMAIN_TYPE = new TextEncoder().encode("EIP712Domain(string name,string version,uint256 chainId,bytes32 salt)");
+export const EIP712_REQUEST_TYPE = new TextEncoder().encode("WithdrawRequest(bytes32 vault,bytes32 user,bytes32 withdrawToken,bytes32 withdrawTokenAccount,bytes32 token,uint64 sequence,uint64 shares,uint64 nav,uint64 withdrawAmount)");
+export const EIP712_FILL_TYPE = new TextEncoder().encode("WithdrawFill(bytes32 request,uint64 filledAmount,uint64 amount)");
+export const EIP712_SWITCH_TYPE = new TextEncoder().encode("WithdrawSwitch(bytes32 request,uint64 filledAmount,bytes32 withdrawToken,bytes32 withdrawTokenAccount)");
+
+export const PAUSE_DEPOSIT = 1 << 0
+export const PAUSE_WITHDRAW_REQUEST = 1 << 1
+export const PAUSE_WITHDRAW = 1 << 2
+export const PAUSE_MINT = 1 << 3
+
 export function ecdsaPubkeyFromPrivkey(privkey: Uint8Array): Uint8Array {
     return secp256k1.getPublicKey(privkey, false)
 }
 
+export function schnorrPubkeyFromPrivkey(privkey: Uint8Array): Uint8Array {
+    return schnorr.getPublicKey(privkey)
+}
+
+export function ethereumAddressFromPrivkey(privkey: Uint8Array): Uint8Array {
+    return keccak_256(secp256k1.getPublicKey(privkey, false).slice(1)).slice(12)
+}
+
 export function derivePoolSignerAddress(mint: PublicKey): PublicKey {
   return PublicKey.findProgramAddressSync(
     [
@@ -51,37 +78,178 @@ export function deriveMinterManagerAddress(vault: PublicKey): PublicKey {
   )[0]
 }
 
-export function deriveWithdrawRequestAddress(vault: PublicKey, withdrawMint: PublicKey, user: PublicKey, hash: Uint8Array): PublicKey {
-  if (hash.length != 32) {
-    throw new Error("Invalid hash length, expected 32")
-  }
+export function deriveUserDepositAddress(vault: PublicKey, user: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [
+      USER_DEPOSIT_SEED,
+      vault.toBytes(),
+      user.toBytes()
+    ],
+    SOLVBTC_PROGRAM_ID    
+  )[0]
+}
+
+export function deriveWithdrawRequestAddress(vault: PublicKey, sequence: BN): PublicKey {
   return PublicKey.findProgramAddressSync(
     [
       WITHDRAW_REQUEST_SEED,
       vault.toBytes(),
-      withdrawMint.toBytes(),
-      user.toBytes(),
-      hash
+      sequence.toArrayLike(Buffer, 'le', 8)
+    ],
+    SOLVBTC_PROGRAM_ID    
+  )[0]
+}
+
+export function deriveRedemptionEpochAddress(vault: PublicKey, epoch: BN): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [
+      REDEMPTION_EPOCH_SEED,
+      vault.toBytes(),
+      epoch.toArrayLike(Buffer, 'le', 8)
     ],
     SOLVBTC_PROGRAM_ID    
   )[0]
 }
 
-/// Create a random 32-byte scalar for the request
-export function createWithdrawRequestHash(): Uint8Array {
-  return Keypair.generate().secretKey.subarray(0, 32)
+export function deriveAddressBookAddress(vault: PublicKey, user: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [
+      ADDRESS_BOOK_SEED,
+      vault.toBytes(),
+      user.toBytes()
+    ],
+    SOLVBTC_PROGRAM_ID    
+  )[0]
+}
+
+export function deriveVerifierSetAddress(vault: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [
+      VERIFIER_SET_SEED,
+      vault.toBytes()
+    ],
+    SOLVBTC_PROGRAM_ID    
+  )[0]
 }
 
-export function deriveWithdrawRequestSigningHash(user: PublicKey, mint: PublicKey, hash: Uint8Array, shares: BN, nav: BN): Uint8Array {
+export function deriveWithdrawRequestSigningHash(
+  programId: PublicKey,
+  chainId: BN,
+  vault: PublicKey,
+  user: PublicKey,
+  mint: PublicKey,
+  tokenAccount: PublicKey,
+  targetMint: PublicKey,
+  sequence: BN,
+  shares: BN,
+  nav: BN,
+  withdrawAmount: BN
+): Uint8Array {
   return sha256(new Uint8Array([
+    ...WITHDRAW_MESSAGE_DOMAIN,
+    WITHDRAW_MESSAGE_VERSION,
+    ...chainId.toArrayLike(Buffer, 'le', 8),
+    ...programId.toBytes(),
+    ...vault.toBytes(),
     ...user.toBytes(),
     ...mint.toBytes(),
-    ...hash,
+    ...tokenAccount.toBytes(),
+    ...targetMint.toBytes(),
+    ...sequence.toArrayLike(Buffer, 'le', 8),
     ...shares.toArrayLike(Buffer, 'le', 8),
     ...nav.toArrayLike(Buffer, 'le', 8),
+    ...withdrawAmount.toArrayLike(Buffer, 'le', 8),
+  ]))
+}
+
+export function deriveWithdrawFillSigningHash(requestSigningHash: Uint8Array, filledAmount: BN, amount: BN): Uint8Array {
+  return sha256(new Uint8Array([
+    ...requestSigningHash,
+    ...filledAmount.toArrayLike(Buffer, 'le', 8),
+    ...amount.toArrayLike(Buffer, 'le', 8),
+  ]))
+}
+
+export function deriveWithdrawSwitchSigningHash(requestSigningHash: Uint8Array, filledAmount: BN, mint: PublicKey, tokenAccount: PublicKey): Uint8Array {
+  return sha256(new Uint8Array([
+    ...requestSigningHash,
+    ...filledAmount.toArrayLike(Buffer, 'le', 8),
+    ...mint.toBytes(),
+    ...tokenAccount.toBytes(),
+  ]))
+}
+
+/// EIP-712 struct hash of a withdraw request, the `request` field of fill and switch approvals
+export function deriveEip712WithdrawRequestHash(
+  vault: PublicKey,
+  user: PublicKey,
+  mint: PublicKey,
+  tokenAccount: PublicKey,
+  targetMint: PublicKey,
+  sequence: BN,
+  shares: BN,
+  nav: BN,
+  withdrawAmount: BN
+): Uint8Array {
+  return keccak_256(new Uint8Array([
+    ...keccak_256(EIP712_REQUEST_TYPE),
+    ...vault.toBytes(),
+    ...user.toBytes(),
+    ...mint.toBytes(),
+    ...tokenAccount.toBytes(),
+    ...targetMint.toBytes(),
+    ...sequence.toArrayLike(Buffer, 'be', 32),
+    ...shares.toArrayLike(Buffer, 'be', 32),
+    ...nav.toArrayLike(Buffer, 'be', 32),
+    ...withdrawAmount.toArrayLike(Buffer, 'be', 32),
   ]))
 }
 
+export function deriveEip712WithdrawFillHash(requestHash: Uint8Array, filledAmount: BN, amount: BN): Uint8Array {
+  return keccak_256(new Uint8Array([
+    ...keccak_256(EIP712_FILL_TYPE),
+    ...requestHash,
+    ...filledAmount.toArrayLike(Buffer, 'be', 32),
+    ...amount.toArrayLike(Buffer, 'be', 32),
+  ]))
+}
+
+export function deriveEip712WithdrawSwitchHash(requestHash: Uint8Array, filledAmount: BN, mint: PublicKey, tokenAccount: PublicKey): Uint8Array {
+  return keccak_256(new Uint8Array([
+    ...keccak_256(EIP712_SWITCH_TYPE),
+    ...requestHash,
+    ...filledAmount.toArrayLike(Buffer, 'be', 32),
+    ...mint.toBytes(),
+    ...tokenAccount.toBytes(),
+  ]))
+}
+
+/// Typed data digest signed by an Ethereum verifier under the chain ID of the vault, salted with the program id
+export function deriveEip712Digest(programId: PublicKey, chainId: BN, structHash: Uint8Array): Uint8Array {
+  const domainSeparator = keccak_256(new Uint8Array([
+    ...keccak_256(EIP712_DOMAIN_TYPE),
+    ...keccak_256(WITHDRAW_MESSAGE_DOMAIN),
+    ...keccak_256(EIP712_DOMAIN_VERSION),
+    ...chainId.toArrayLike(Buffer, 'be', 32),
+    ...programId.toBytes(),
+  ]))
+  return keccak_256(new Uint8Array([0x19, 0x01, ...domainSeparator, ...structHash]))
+}
+
+/// Sign an EIP-712 digest, the verifier recovers the signer from the 64-byte low-s signature
+export function createEip712WithdrawSignature(privkey: Uint8Array, digest: Uint8Array): number[] {
+  const signature = secp256k1.sign(digest, privkey, {
+    lowS: true,
+  });
+
+  return new Array(...signature.toBytes("compact"));
+}
+
+/// BIP-340 signature over a withdraw signing hash, verified against an x-only key
+export function createSchnorrWithdrawSignature(privkey: Uint8Array, hash: Uint8Array): number[] {
+  return new Array(...schnorr.sign(hash, privkey));
+}
+
 export function createWithdrawSignature(privkey: Uint8Array, hash: Uint8Array): {
   isOdd: boolean;
   signature: number[];
@@ -1,10 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Solvbtc } from "../target/types/solvbtc";
-import { Keypair, LAMPORTS_PER_SOL, SystemProgram, Transaction } from "@solana/web3.js";
-import { createWithdrawRequestHash, createWithdrawSignature, deriveMinterManagerAddress, derivePoolSignerAddress, deriveVaultAddress, deriveWithdrawRequestAddress, deriveWithdrawRequestSigningHash, ecdsaPubkeyFromPrivkey, ONE_BITCOIN } from "../sdk/solvbtc";
+import { ComputeBudgetProgram, Ed25519Program, Keypair, LAMPORTS_PER_SOL, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY, Transaction } from "@solana/web3.js";
+import { ed25519 } from "@noble/curves/ed25519";
+import { createEip712WithdrawSignature, createSchnorrWithdrawSignature, createWithdrawSignature, deriveAddressBookAddress, deriveEip712Digest, deriveEip712WithdrawRequestHash, deriveMinterManagerAddress, derivePoolSignerAddress, deriveRedemptionEpochAddress, deriveUserDepositAddress, deriveVaultAddress, deriveVerifierSetAddress, deriveWithdrawFillSigningHash, deriveWithdrawRequestAddress, deriveWithdrawRequestSigningHash, deriveWithdrawSwitchSigningHash, ecdsaPubkeyFromPrivkey, ethereumAddressFromPrivkey, ONE_BITCOIN, schnorrPubkeyFromPrivkey, PAUSE_DEPOSIT } from "../sdk/solvbtc";
 import { BN } from "bn.js";
-import { createAssociatedTokenAccountIdempotentInstruction, createInitializeMint2Instruction, createInitializeMultisigInstruction, createTransferCheckedInstruction, getAssociatedTokenAddressSync, getMinimumBalanceForRentExemptMint, getMinimumBalanceForRentExemptMultisig, MINT_SIZE, MULTISIG_SIZE, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { createAssociatedTokenAccountIdempotentInstruction, createInitializeMint2Instruction, createInitializeMultisigInstruction, createMintToInstruction, createTransferCheckedInstruction, getAccount, getAssociatedTokenAddressSync, getMint, getMinimumBalanceForRentExemptMint, getMinimumBalanceForRentExemptMultisig, MINT_SIZE, MULTISIG_SIZE, TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { ASSOCIATED_PROGRAM_ID } from "@coral-xyz/anchor/dist/cjs/utils/token";
 
 describe("solvbtc", () => {
@@ -52,6 +53,8 @@ describe("solvbtc", () => {
   const userKeypair = Keypair.generate()
   const mintAKeypair = Keypair.generate()
   const mintBKeypair = Keypair.generate()
+  const mintCKeypair = Keypair.generate()
+  const mintDKeypair = Keypair.generate()
   const multisigAKeypair = Keypair.generate()
   const multisigBKeypair = Keypair.generate()
 
@@ -64,6 +67,8 @@ describe("solvbtc", () => {
   const user = userKeypair.publicKey
   const mintA = mintAKeypair.publicKey
   const mintB = mintBKeypair.publicKey
+  const mintC = mintCKeypair.publicKey
+  const mintD = mintDKeypair.publicKey
   const multisigA = multisigAKeypair.publicKey
   const multisigB = multisigBKeypair.publicKey
   const poolSignerA = derivePoolSignerAddress(mintA)
@@ -72,10 +77,16 @@ describe("solvbtc", () => {
   const vaultB = deriveVaultAddress(mintB)
   const minterManagerA = deriveMinterManagerAddress(vaultA)
   const minterManagerB = deriveMinterManagerAddress(vaultB)
+  const userDepositA = deriveUserDepositAddress(vaultA, user)
+  const addressBookA = deriveAddressBookAddress(vaultA, user)
+  const verifierSetA = deriveVerifierSetAddress(vaultA)
 
-  // Withdraw request
-  const hash = createWithdrawRequestHash();
-  const withdrawRequest = deriveWithdrawRequestAddress(vaultA, mintB, user, hash);
+  // Withdraw request, the first request of vault A is assigned sequence 0
+  const sequence = new BN(0);
+  const withdrawRequest = deriveWithdrawRequestAddress(vaultA, sequence);
+
+  // Chain ID of the local deployment, bound into signed withdraw messages
+  const chainId = new BN(3);
 
   // Programs
   const tokenProgram = TOKEN_PROGRAM_ID
@@ -89,6 +100,12 @@ describe("solvbtc", () => {
   const authorityAtaB = getAssociatedTokenAddressSync(mintB, authority)
   const vaultAAtaA = getAssociatedTokenAddressSync(mintA, vaultA, true)
   const vaultAAtaB = getAssociatedTokenAddressSync(mintB, vaultA, true)
+  const userAtaC = getAssociatedTokenAddressSync(mintC, user)
+  const authorityAtaC = getAssociatedTokenAddressSync(mintC, authority)
+  const vaultAAtaC = getAssociatedTokenAddressSync(mintC, vaultA, true)
+  const userAtaD = getAssociatedTokenAddressSync(mintD, user)
+  const authorityAtaD = getAssociatedTokenAddressSync(mintD, authority)
+  const vaultAAtaD = getAssociatedTokenAddressSync(mintD, vaultA, true)
 
   /// ECDSA Keys
   let verifierKeypair = Buffer.from("c2fffbf8e5cec943afb99e8194a5819c64c9df75b4ed03b2a111e8ccdcf55689", "hex")
@@ -107,6 +124,10 @@ describe("solvbtc", () => {
     multisigB,
     user,
     withdrawRequest,
+    redemptionEpoch: null,
+    addressBook: addressBookA,
+    verifierSet: verifierSetA,
+    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
     tokenProgram,
     associatedTokenProgram,
     systemProgram
@@ -182,6 +203,7 @@ describe("solvbtc", () => {
       oracleManager,
       ONE_BITCOIN,
       50,
+      chainId,
     )
       .accountsStrict({
         ...accounts,
@@ -205,6 +227,7 @@ describe("solvbtc", () => {
       authority,
       ONE_BITCOIN,
       50,
+      chainId,
     )
       .accountsStrict({
         ...accounts,
@@ -253,11 +276,12 @@ describe("solvbtc", () => {
 
   it("Add vault currency", async () => {
     const tx = await program.methods.vaultAddCurrency(
-      mintB,
-      75
+      75,
+      ONE_BITCOIN
     )
       .accountsStrict({
         ...accounts,
+        currency: mintB,
         admin: authority,
         payer: authority,
         vault: vaultA,
@@ -272,11 +296,12 @@ describe("solvbtc", () => {
   it("Fail to add existing vault currency", async () => {
     try {
       await program.methods.vaultAddCurrency(
-        mintB,
-        50
+        50,
+        ONE_BITCOIN
       )
         .accountsStrict({
           ...accounts,
+          currency: mintB,
           admin: authority,
           payer: authority,
           vault: vaultA,
@@ -296,11 +321,12 @@ describe("solvbtc", () => {
 
   it("Add vault currency for removal", async () => {
     const tx = await program.methods.vaultAddCurrency(
-      mintA,
-      50
+      50,
+      ONE_BITCOIN
     )
       .accountsStrict({
         ...accounts,
+        currency: mintA,
         admin: authority,
         payer: authority,
         vault: vaultA,
@@ -600,6 +626,12 @@ describe("solvbtc", () => {
           authorityAtaB,
           authority,
           mintB
+        ),
+        createAssociatedTokenAccountIdempotentInstruction(
+          provider.publicKey,
+          authorityAtaA,
+          authority,
+          mintA
         )
       ])
 
@@ -610,7 +642,8 @@ describe("solvbtc", () => {
         userTokenTa: userAtaB,
         userTargetTa: userAtaA,
         treasurerTokenTa: authorityAtaB,
-        // feeReceiverTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: userDepositA,
         mintToken: mintB,
         mintTarget: mintA,
       })
@@ -663,10 +696,35 @@ describe("solvbtc", () => {
     }
   });
 
-  it("Fail to set invalid NAV", async () => {
+  it("Set and Check currency rate", async () => {
+    const tx = await program.methods.vaultSetCurrencyRate(
+      mintB,
+      ONE_BITCOIN
+    )
+    .accountsStrict({
+      ...accounts,
+      oracleManager: authority,
+      vault: vaultA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const vault = await program.account.vault.fetch(
+      vaultA
+    )
+
+    if (vault.currencyConfigs[0].rate.toNumber() != ONE_BITCOIN.toNumber()) {
+      throw new Error("Invalid currency rate")
+    }
+  });
+
+  it("Fail to set currency rate beyond max change", async () => {
     try {
-      const tx = await program.methods.vaultSetNav(
-        ONE_BITCOIN.sub(new BN(1))
+      const tx = await program.methods.vaultSetCurrencyRate(
+        mintB,
+        ONE_BITCOIN.muln(2)
       )
       .accountsStrict({
         ...accounts,
@@ -679,125 +737,2590 @@ describe("solvbtc", () => {
       .then(log)
       throw new Error("This shouldn't succeed")
     } catch(e) {
-      if (e.error.errorMessage != "SolvOracle: Invalid NAV value - must be >= 1 Bitcoin") {
+      if (e.error.errorMessage != "SolvOracle: Invalid currency rate") {
         throw new Error("Unexpected error message")
       }
     }
   });
-  
-  it("Deposit Token B to vault A", async () => {
-    const tx = await program.methods.vaultDeposit(
-      new BN(5_000_000), 
-      new BN(4_000_000)
-    )
-    .preInstructions([
-      createAssociatedTokenAccountIdempotentInstruction(
-        provider.publicKey,
-        userAtaA,
-        user,
-        mintA
-      ),
-      createAssociatedTokenAccountIdempotentInstruction(
-        provider.publicKey,
-        authorityAtaB,
-        authority,
-        mintB
-      )
-    ])
 
-    .accountsStrict({
+  it("Fail to set a zero currency rate", async () => {
+    const updateAccounts = {
       ...accounts,
+      admin: authority,
+      payer: authority,
       vault: vaultA,
-      multisig: multisigA,
-      userTokenTa: userAtaB,
-      userTargetTa: userAtaA,
-      treasurerTokenTa: authorityAtaB,
-      // feeReceiverTokenTa: authorityAtaB,
-      mintToken: mintB,
-      mintTarget: mintA,
-    })
-    .signers([userKeypair])
-    .rpc()
-    .then(confirm)
-    .then(log)
-  })
+      mint: mintA
+    }
+    // Allow a full change so only the zero rate check applies
+    await program.methods.vaultSetMaxNavChange(
+      10_000
+    )
+      .accountsStrict(updateAccounts)
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
 
-  it("Create withdraw request", async () => {
-    const tx = await program.methods.vaultWithdrawRequest(
-      Array.from(hash),
-      new BN(500_000)
+    try {
+      await program.methods.vaultSetCurrencyRate(
+        mintB,
+        new BN(0)
+      )
+      .accountsStrict({
+        ...accounts,
+        oracleManager: authority,
+        vault: vaultA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvOracle: Invalid currency rate") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultSetMaxNavChange(
+      5
     )
-    .accountsStrict({
-      ...accounts,
-      vault: vaultA,
-      userTargetTa: userAtaA,
-      mintTarget: mintA,
-      userWithdrawTa: userAtaB,
-      mintWithdraw: mintB
-    })
-    .signers([userKeypair])
-    .rpc()
-    .then(confirm)
-    .then(log)
-  })
+      .accountsStrict(updateAccounts)
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  });
 
-  it("Rebalance liquidity from treasurer back to vault", async () => {
-    let tx = new Transaction();
-    tx.instructions = [
-      createAssociatedTokenAccountIdempotentInstruction(
-          provider.publicKey,
-          vaultAAtaB,
-          vaultA,
-          mintB
-        ),
+  it("Set vault max NAV age", async () => {
+    const tx = await program.methods.vaultSetMaxNavAge(
+      3600
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    const vault = await program.account.vault.fetch(
+      vaultA
+    )
+
+    if (vault.maxNavAge != 3600) {
+      throw new Error("Invalid max NAV age")
+    }
+  });
+
+  it("Fail to deposit Token B to vault A with a stale NAV", async () => {
+    await program.methods.vaultSetMaxNavAge(
+      1
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    // Let the NAV age past the maximum
+    await new Promise((resolve) => setTimeout(resolve, 3000))
+
+    try {
+      await program.methods.vaultDeposit(
+        new BN(5_000_000),
+        new BN(4_000_000)
+      )
+      .preInstructions([
         createAssociatedTokenAccountIdempotentInstruction(
           provider.publicKey,
-          feeReceiverAtaB,
-          feeReceiver,
-          mintB
-        ),
-        createTransferCheckedInstruction(
-          authorityAtaB,
-          mintB,
-          vaultAAtaB,
+          authorityAtaA,
           authority,
-          600_000,
-          8
+          mintA
         )
-      ]
-      await provider.sendAndConfirm(tx, [authorityKeypair]).then(log);
-  })
+      ])
+      .accountsStrict({
+        ...accounts,
+        vault: vaultA,
+        multisig: multisigA,
+        userTokenTa: userAtaB,
+        userTargetTa: userAtaA,
+        treasurerTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: userDepositA,
+        mintToken: mintB,
+        mintTarget: mintA,
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvOracle: NAV is stale") {
+        throw new Error("Unexpected error message")
+      }
+    }
 
-  it("Process withdraw request", async () => {
-    const withdrawRequestData = await program.account.withdrawRequest.fetch(withdrawRequest);
+    await program.methods.vaultSetMaxNavAge(
+      3600
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  });
 
-    const verifierHash = deriveWithdrawRequestSigningHash(
-      user,
-      mintB,
-      hash,
-      withdrawRequestData.shares,
-      withdrawRequestData.nav,
+  it("Set vault NAV bounds", async () => {
+    await program.methods.vaultSetWithdrawNavTolerance(
+      50
     )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
 
-    const signature = createWithdrawSignature(
-      verifierKeypair,
-      verifierHash
+    await program.methods.vaultSetMaxNavChange(
+      10
     )
-    const tx = await program.methods.vaultWithdraw(
-      Array.from(hash),
-      signature.signature
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    const vault = await program.account.vault.fetch(
+      vaultA
     )
-    .accountsStrict({
-      ...accounts,
-      vault: vaultA,
-      userWithdrawTa: userAtaB,
-      mintWithdraw: mintB,
-      vaultWithdrawTa: vaultAAtaB,
-      feeReceiverTa: authorityAtaB
+
+    if (vault.withdrawNavTolerance != 50 || vault.maxNavChange != 10) {
+      throw new Error("Invalid NAV bounds")
+    }
+  });
+
+  it("Fail to set max NAV change above 100%", async () => {
+    try {
+      await program.methods.vaultSetMaxNavChange(
+        10_001
+      )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvOracle: Invalid Max NAV Change - must be <=10,000") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  });
+
+  it("Fail to set invalid NAV", async () => {
+    try {
+      const tx = await program.methods.vaultSetNav(
+        ONE_BITCOIN.sub(new BN(1))
+      )
+      .accountsStrict({
+        ...accounts,
+        oracleManager: authority,
+        vault: vaultA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvOracle: Invalid NAV value - must be >= 1 Bitcoin") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  });
+  
+  it("Fail to set NAV below 1 Bitcoin with a full max NAV change", async () => {
+    const updateAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    const maxNavChange = (await program.account.vault.fetch(vaultA)).maxNavChange
+    await program.methods.vaultSetMaxNavChange(
+      10_000
+    )
+      .accountsStrict(updateAccounts)
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    try {
+      await program.methods.vaultSetNav(
+        new BN(0)
+      )
+      .accountsStrict({
+        ...accounts,
+        oracleManager: authority,
+        vault: vaultA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvOracle: Invalid NAV value - must be >= 1 Bitcoin") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultSetMaxNavChange(
+      maxNavChange
+    )
+      .accountsStrict(updateAccounts)
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  });
+
+  it("Fail to deposit Token B to vault A above the currency deposit cap", async () => {
+    await program.methods.vaultSetDepositCap(
+      mintB,
+      new BN(1_000_000)
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    try {
+      await program.methods.vaultDeposit(
+        new BN(5_000_000),
+        new BN(4_000_000)
+      )
+      .preInstructions([
+        createAssociatedTokenAccountIdempotentInstruction(
+          provider.publicKey,
+          authorityAtaA,
+          authority,
+          mintA
+        )
+      ])
+      .accountsStrict({
+        ...accounts,
+        vault: vaultA,
+        multisig: multisigA,
+        userTokenTa: userAtaB,
+        userTargetTa: userAtaA,
+        treasurerTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: userDepositA,
+        mintToken: mintB,
+        mintTarget: mintA,
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Currency deposit cap exceeded") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultSetDepositCap(
+      mintB,
+      new BN(0)
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  })
+
+  it("Fail to deposit Token B to vault A above the vault share cap", async () => {
+    // Cap the target supply below what the deposit would mint
+    const supply = (await getMint(connection, mintA)).supply
+    await program.methods.vaultSetMaxTotalShares(
+      new BN((supply + BigInt(1_000_000)).toString())
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    try {
+      await program.methods.vaultDeposit(
+        new BN(5_000_000),
+        new BN(4_000_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        vault: vaultA,
+        multisig: multisigA,
+        userTokenTa: userAtaB,
+        userTargetTa: userAtaA,
+        treasurerTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: userDepositA,
+        mintToken: mintB,
+        mintTarget: mintA,
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Vault share cap exceeded") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultSetMaxTotalShares(
+      new BN(0)
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  })
+
+  it("Fail to deposit Token B to vault A above the user share cap", async () => {
+    await program.methods.vaultSetMaxUserShares(
+      new BN(1_000_000)
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    try {
+      await program.methods.vaultDeposit(
+        new BN(5_000_000),
+        new BN(4_000_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        vault: vaultA,
+        multisig: multisigA,
+        userTokenTa: userAtaB,
+        userTargetTa: userAtaA,
+        treasurerTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: userDepositA,
+        mintToken: mintB,
+        mintTarget: mintA,
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: User share cap exceeded") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    // Shares per user can only be tracked with the user deposit account
+    try {
+      await program.methods.vaultDeposit(
+        new BN(5_000_000),
+        new BN(4_000_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        vault: vaultA,
+        multisig: multisigA,
+        userTokenTa: userAtaB,
+        userTargetTa: userAtaA,
+        treasurerTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: null,
+        mintToken: mintB,
+        mintTarget: mintA,
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: User deposit account required") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultSetMaxUserShares(
+      new BN(0)
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  })
+
+  it("Fail to deposit Token B to vault A while deposits are paused", async () => {
+    await program.methods.vaultSetPaused(
+      PAUSE_DEPOSIT
+    )
+      .accountsStrict({
+        authority,
+        vault: vaultA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    try {
+      await program.methods.vaultDeposit(
+        new BN(5_000_000),
+        new BN(4_000_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        vault: vaultA,
+        multisig: multisigA,
+        userTokenTa: userAtaB,
+        userTargetTa: userAtaA,
+        treasurerTokenTa: authorityAtaB,
+        feeReceiverTargetTa: authorityAtaA,
+        userDeposit: userDepositA,
+        mintToken: mintB,
+        mintTarget: mintA,
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Operation paused") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultSetPaused(
+      0
+    )
+      .accountsStrict({
+        authority,
+        vault: vaultA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  })
+
+  it("Deposit Token B to vault A", async () => {
+    const tx = await program.methods.vaultDeposit(
+      new BN(5_000_000), 
+      new BN(4_000_000)
+    )
+    .preInstructions([
+      createAssociatedTokenAccountIdempotentInstruction(
+        provider.publicKey,
+        userAtaA,
+        user,
+        mintA
+      ),
+      createAssociatedTokenAccountIdempotentInstruction(
+        provider.publicKey,
+        authorityAtaB,
+        authority,
+        mintB
+      ),
+      createAssociatedTokenAccountIdempotentInstruction(
+        provider.publicKey,
+        authorityAtaA,
+        authority,
+        mintA
+      )
+    ])
+
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA,
+      multisig: multisigA,
+      userTokenTa: userAtaB,
+      userTargetTa: userAtaA,
+      treasurerTokenTa: authorityAtaB,
+      feeReceiverTargetTa: authorityAtaA,
+      userDeposit: userDepositA,
+      mintToken: mintB,
+      mintTarget: mintA,
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // 5% deposit fee is minted to the fee receiver as target shares
+    const feeReceiverTa = await getAccount(connection, authorityAtaA)
+    if (feeReceiverTa.amount != BigInt(250_000)) {
+      throw new Error("Invalid deposit fee")
+    }
+  })
+
+  it("Create withdraw request", async () => {
+    const tx = await program.methods.vaultWithdrawRequest(
+      new BN(500_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Cancel withdraw request", async () => {
+    const cancelSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const cancelRequest = deriveWithdrawRequestAddress(vaultA, cancelSequence);
+    const before = await getAccount(connection, userAtaA)
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(100_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: cancelRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    await program.methods.vaultCancelWithdrawRequest(
+      cancelSequence
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: cancelRequest,
+      vault: vaultA,
+      multisig: multisigA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const after = await getAccount(connection, userAtaA)
+    if (after.amount != before.amount) {
+      throw new Error("Shares not restored")
+    }
+  })
+
+  it("Rebalance liquidity from treasurer back to vault", async () => {
+    let tx = new Transaction();
+    tx.instructions = [
+      createAssociatedTokenAccountIdempotentInstruction(
+          provider.publicKey,
+          vaultAAtaB,
+          vaultA,
+          mintB
+        ),
+        createAssociatedTokenAccountIdempotentInstruction(
+          provider.publicKey,
+          feeReceiverAtaB,
+          feeReceiver,
+          mintB
+        ),
+        createTransferCheckedInstruction(
+          authorityAtaB,
+          mintB,
+          vaultAAtaB,
+          authority,
+          600_000,
+          8
+        )
+      ]
+      await provider.sendAndConfirm(tx, [authorityKeypair]).then(log);
+  })
+
+  it("Set vault withdraw expiry", async () => {
+    const tx = await program.methods.vaultSetWithdrawExpiry(
+      0,
+      { restoreShares: {} }
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+  })
+
+  it("Set vault chain ID", async () => {
+    try {
+      await program.methods.vaultSetChainId(
+        new BN(0)
+      )
+        .accountsStrict({
+          ...accounts,
+          admin: authority,
+          payer: authority,
+          vault: vaultA,
+          mint: mintA
+        })
+        .signers([authorityKeypair])
+        .rpc()
+        .then(confirm)
+        .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Invalid chain ID") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    for (const id of [chainId.addn(1), chainId]) {
+      await program.methods.vaultSetChainId(
+        id
+      )
+        .accountsStrict({
+          ...accounts,
+          admin: authority,
+          payer: authority,
+          vault: vaultA,
+          mint: mintA
+        })
+        .signers([authorityKeypair])
+        .rpc()
+        .then(confirm)
+        .then(log)
+
+      const vault = await program.account.vault.fetch(vaultA)
+      if (!vault.chainId.eq(id)) {
+        throw new Error("Invalid chain ID")
+      }
+    }
+  })
+
+  it("Set vault settlement policy", async () => {
+    const tx = await program.methods.vaultSetSettlementPolicy(
+      { lowerNav: {} }
+    )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    const vault = await program.account.vault.fetch(vaultA)
+    if (!("lowerNav" in vault.settlementPolicy)) {
+      throw new Error("Invalid settlement policy")
+    }
+  })
+
+  it("Fail to expire withdraw request that has not expired", async () => {
+    try {
+      await program.methods.vaultExpireWithdrawRequest(
+        sequence
+      )
+      .accountsStrict({
+        ...accounts,
+        keeper: payer,
+        vault: vaultA,
+        multisig: multisigA,
+        recipientTargetTa: userAtaA,
+        mintTarget: mintA,
+        mintWithdraw: mintB
+      })
+      .signers([payerKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Withdraw request not expired") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  })
+
+  it("Partially process withdraw request", async () => {
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(withdrawRequest);
+    const amount = new BN(200_000)
+
+    const verifierHash = deriveWithdrawFillSigningHash(
+      deriveWithdrawRequestSigningHash(
+        program.programId,
+        chainId,
+        vaultA,
+        user,
+        mintB,
+        userAtaB,
+        mintA,
+        sequence,
+        withdrawRequestData.shares,
+        withdrawRequestData.nav,
+        withdrawRequestData.withdrawAmount,
+      ),
+      withdrawRequestData.filledAmount,
+      amount
+    )
+
+    const signature = createWithdrawSignature(
+      verifierKeypair,
+      verifierHash
+    )
+    const tx = await program.methods.vaultWithdrawPartial(
+      sequence,
+      amount,
+      [{ signer: 0, signature: signature.signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const filledRequestData = await program.account.withdrawRequest.fetch(withdrawRequest);
+    if (!filledRequestData.filledAmount.eq(amount)) {
+      throw new Error("Invalid filled amount")
+    }
+  })
+
+  it("Process withdraw request", async () => {
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(withdrawRequest);
+
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      sequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+
+    const signature = createWithdrawSignature(
+      verifierKeypair,
+      verifierHash
+    )
+    const tx = await program.methods.vaultWithdraw(
+      sequence,
+      [{ signer: 0, signature: signature.signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Process withdraw request by keeper", async () => {
+    const keeperSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const keeperRequest = deriveWithdrawRequestAddress(vaultA, keeperSequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: keeperRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(keeperRequest);
+
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      keeperSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+
+    const signature = createWithdrawSignature(
+      verifierKeypair,
+      verifierHash
+    )
+    const tx = await program.methods.vaultWithdrawFor(
+      keeperSequence,
+      [{ signer: 0, signature: signature.signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      keeper: payer,
+      withdrawRequest: keeperRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([payerKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Process withdraw request at the lower NAV after a drop beyond the NAV tolerance", async () => {
+    const updateAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    const oracleAccounts = {
+      ...accounts,
+      oracleManager: authority,
+      vault: vaultA
+    }
+    const vaultData = await program.account.vault.fetch(vaultA)
+    const nav = vaultData.nav
+    const requestNav = nav.muln(2)
+
+    await program.methods.vaultSetMaxNavChange(10_000)
+    .accountsStrict(updateAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    await program.methods.vaultSetNav(requestNav)
+    .accountsStrict(oracleAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const lowerNavSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const lowerNavRequest = deriveWithdrawRequestAddress(vaultA, lowerNavSequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: lowerNavRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Halve the NAV, far beyond the withdraw NAV tolerance
+    await program.methods.vaultSetNav(nav)
+    .accountsStrict(oracleAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(lowerNavRequest);
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      lowerNavSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+    const signature = createWithdrawSignature(
+      verifierKeypair,
+      verifierHash
+    )
+    const before = await getAccount(connection, userAtaB)
+
+    await program.methods.vaultWithdraw(
+      lowerNavSequence,
+      [{ signer: 0, signature: signature.signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: lowerNavRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Paid at the lower NAV, less the withdraw fee
+    const payout = withdrawRequestData.withdrawAmount.mul(nav).div(requestNav)
+    const fee = payout.muln(vaultData.withdrawFee).divn(10_000)
+    const after = await getAccount(connection, userAtaB)
+    if (after.amount - before.amount != BigInt(payout.sub(fee).toString())) {
+      throw new Error("Withdrawal not paid at the lower NAV")
+    }
+
+    await program.methods.vaultSetMaxNavChange(vaultData.maxNavChange)
+    .accountsStrict(updateAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Process withdraw request with an EIP-712 verifier", async () => {
+    const setVerifierAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    await program.methods.vaultSetEip712Verifier(
+      Array.from(ethereumAddressFromPrivkey(verifierKeypair))
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const eip712Sequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const eip712Request = deriveWithdrawRequestAddress(vaultA, eip712Sequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: eip712Request,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(eip712Request);
+
+    const digest = deriveEip712Digest(
+      program.programId,
+      chainId,
+      deriveEip712WithdrawRequestHash(
+        vaultA,
+        user,
+        mintB,
+        userAtaB,
+        mintA,
+        eip712Sequence,
+        withdrawRequestData.shares,
+        withdrawRequestData.nav,
+        withdrawRequestData.withdrawAmount,
+      )
+    )
+
+    await program.methods.vaultWithdraw(
+      eip712Sequence,
+      [{ signer: 0, signature: createEip712WithdrawSignature(verifierKeypair, digest) }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: eip712Request,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Restore the ECDSA verifier for the remaining tests
+    await program.methods.vaultSetVerifier(
+      verifier
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Fail to add a verifier set member with a key in the wrong format", async () => {
+    try {
+      await program.methods.vaultAddVerifier(
+        Array.from(ecdsaPubkeyFromPrivkey(verifierKeypair).subarray(1)),
+        { ed25519: {} }
+      )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Invalid verifier key format") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  })
+
+  it("Process withdraw request approved by a 2-of-3 verifier set", async () => {
+    const memberKeypairs = [verifierKeypair, Buffer.alloc(32, 1), Buffer.alloc(32, 2)]
+    const updateVerifierSetAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    for (const memberKeypair of memberKeypairs) {
+      await program.methods.vaultAddVerifier(
+        Array.from(ecdsaPubkeyFromPrivkey(memberKeypair).subarray(1)),
+        { secp256k1Ecdsa: {} }
+      )
+      .accountsStrict(updateVerifierSetAccounts)
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+    }
+    await program.methods.vaultSetVerifierThreshold(2)
+    .accountsStrict(updateVerifierSetAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const thresholdSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const thresholdRequest = deriveWithdrawRequestAddress(vaultA, thresholdSequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: thresholdRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(thresholdRequest);
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      thresholdSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+    const memberSignatures = memberKeypairs.map((memberKeypair, signer) => (
+      { signer, signature: createWithdrawSignature(memberKeypair, verifierHash).signature }
+    ))
+    const withdrawAccounts = {
+      ...accounts,
+      withdrawRequest: thresholdRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    }
+
+    // The same member cannot sign twice to meet the threshold
+    try {
+      await program.methods.vaultWithdraw(
+        thresholdSequence,
+        [memberSignatures[0], memberSignatures[0]]
+      )
+      .accountsStrict(withdrawAccounts)
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Duplicate verifier signature") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultWithdraw(
+      thresholdSequence,
+      [memberSignatures[0], memberSignatures[2]]
+    )
+    .accountsStrict(withdrawAccounts)
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Approve the remaining tests with the vault verifier alone
+    await program.methods.vaultSetVerifierThreshold(0)
+    .accountsStrict(updateVerifierSetAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Compare withdraw compute units with secp256k1 recovery", async () => {
+    const setVerifierAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+
+    const withdrawComputeUnits = async () => {
+      const requestSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+      const request = deriveWithdrawRequestAddress(vaultA, requestSequence);
+
+      await program.methods.vaultWithdrawRequest(
+        new BN(50_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        withdrawRequest: request,
+        vault: vaultA,
+        userTargetTa: userAtaA,
+        mintTarget: mintA,
+        userWithdrawTa: userAtaB,
+        mintWithdraw: mintB
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+      const withdrawRequestData = await program.account.withdrawRequest.fetch(request);
+      const verifierHash = deriveWithdrawRequestSigningHash(
+        program.programId,
+        chainId,
+        vaultA,
+        user,
+        mintB,
+        userAtaB,
+        mintA,
+        requestSequence,
+        withdrawRequestData.shares,
+        withdrawRequestData.nav,
+        withdrawRequestData.withdrawAmount,
+      )
+      const signature = createWithdrawSignature(
+        verifierKeypair,
+        verifierHash
+      )
+
+      const tx = await program.methods.vaultWithdraw(
+        requestSequence,
+        [{ signer: 0, signature: signature.signature }]
+      )
+      .accountsStrict({
+        ...accounts,
+        withdrawRequest: request,
+        vault: vaultA,
+        userWithdrawTa: userAtaB,
+        mintWithdraw: mintB,
+        vaultWithdrawTa: vaultAAtaB,
+        feeReceiverTa: authorityAtaB
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+      const transaction = await connection.getTransaction(tx, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0
+      });
+      return transaction.meta.computeUnitsConsumed
+    }
+
+    const ecdsaComputeUnits = await withdrawComputeUnits();
+
+    await program.methods.vaultSetRecoverVerifier(
+      verifier
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const recoverComputeUnits = await withdrawComputeUnits();
+    console.log(`Withdraw compute units: ECDSA ${ecdsaComputeUnits}, secp256k1 recovery ${recoverComputeUnits}`)
+    if (recoverComputeUnits >= ecdsaComputeUnits) {
+      throw new Error("Secp256k1 recovery should use fewer compute units")
+    }
+
+    // Restore the ECDSA verifier for the remaining tests
+    await program.methods.vaultSetVerifier(
+      verifier
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Process withdraw request with an Ed25519 verifier", async () => {
+    const ed25519VerifierKeypair = Keypair.generate()
+    const setVerifierAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    await program.methods.vaultSetEd25519Verifier(
+      ed25519VerifierKeypair.publicKey
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const ed25519Sequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const ed25519Request = deriveWithdrawRequestAddress(vaultA, ed25519Sequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: ed25519Request,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(ed25519Request);
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      ed25519Sequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+    const signature = ed25519.sign(verifierHash, ed25519VerifierKeypair.secretKey.slice(0, 32))
+
+    // The Ed25519 program checks the signature in a preceding instruction
+    await program.methods.vaultWithdraw(
+      ed25519Sequence,
+      [{ signer: 0, signature: Array.from(signature) }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: ed25519Request,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .preInstructions([
+      Ed25519Program.createInstructionWithPublicKey({
+        publicKey: ed25519VerifierKeypair.publicKey.toBytes(),
+        message: verifierHash,
+        signature,
+      })
+    ])
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Restore the ECDSA verifier for the remaining tests
+    await program.methods.vaultSetVerifier(
+      verifier
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Process withdraw request with a Schnorr verifier", async () => {
+    const setVerifierAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    await program.methods.vaultSetSchnorrVerifier(
+      Array.from(schnorrPubkeyFromPrivkey(verifierKeypair))
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const schnorrSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const schnorrRequest = deriveWithdrawRequestAddress(vaultA, schnorrSequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: schnorrRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(schnorrRequest);
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      schnorrSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+
+    await program.methods.vaultWithdraw(
+      schnorrSequence,
+      [{ signer: 0, signature: createSchnorrWithdrawSignature(verifierKeypair, verifierHash) }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: schnorrRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .preInstructions([
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+    ])
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Restore the ECDSA verifier for the remaining tests
+    await program.methods.vaultSetVerifier(
+      verifier
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Fail to stage a verifier with a key in the wrong format", async () => {
+    try {
+      await program.methods.vaultStageVerifier(
+        Array.from(ecdsaPubkeyFromPrivkey(verifierKeypair).subarray(1)),
+        { schnorr: {} },
+        new BN(await connection.getBlockTime(await connection.getSlot()) + 3600)
+      )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Invalid verifier key format") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  })
+
+  it("Rotate verifier with an overlap window", async () => {
+    const rotatedVerifierKeypair = Buffer.alloc(32, 3)
+    const setVerifierAccounts = {
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    }
+    await program.methods.vaultSetVerifierOverlap(3600)
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const activatesAt = await connection.getBlockTime(await connection.getSlot()) + 2
+    await program.methods.vaultStageVerifier(
+      Array.from(ecdsaPubkeyFromPrivkey(rotatedVerifierKeypair).subarray(1)),
+      { secp256k1Ecdsa: {} },
+      new BN(activatesAt)
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const rotationSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const rotationRequest = deriveWithdrawRequestAddress(vaultA, rotationSequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(50_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: rotationRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Wait for the staged verifier to activate
+    while (await connection.getBlockTime(await connection.getSlot()) < activatesAt + 1) {
+      await new Promise((resolve) => setTimeout(resolve, 500))
+    }
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(rotationRequest);
+    const requestHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      rotationSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+    const amount = withdrawRequestData.withdrawAmount.divn(2)
+    const withdrawAccounts = {
+      ...accounts,
+      withdrawRequest: rotationRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    }
+
+    // The replaced verifier is still accepted during the overlap window
+    await program.methods.vaultWithdrawPartial(
+      rotationSequence,
+      amount,
+      [{ signer: 0, signature: createWithdrawSignature(verifierKeypair, deriveWithdrawFillSigningHash(requestHash, new BN(0), amount)).signature }]
+    )
+    .accountsStrict(withdrawAccounts)
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const vaultData = await program.account.vault.fetch(vaultA);
+    if (!Buffer.from(vaultData.previousVerifier).equals(Buffer.from(verifier)) || vaultData.previousVerifierExpiry.toNumber() != activatesAt + 3600) {
+      throw new Error("Invalid previous verifier")
+    }
+
+    await program.methods.vaultWithdraw(
+      rotationSequence,
+      [{ signer: 0, signature: createWithdrawSignature(rotatedVerifierKeypair, requestHash).signature }]
+    )
+    .accountsStrict(withdrawAccounts)
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Restore the original verifier for the remaining tests, retiring the rotated one immediately
+    await program.methods.vaultSetVerifier(
+      verifier
+    )
+    .accountsStrict(setVerifierAccounts)
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Process withdraw requests in a batch", async () => {
+    const firstSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const batchSequences = [firstSequence, firstSequence.addn(1)];
+    const batchRequests = batchSequences.map((batchSequence) => deriveWithdrawRequestAddress(vaultA, batchSequence));
+
+    for (const batchRequest of batchRequests) {
+      await program.methods.vaultWithdrawRequest(
+        new BN(10_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        withdrawRequest: batchRequest,
+        vault: vaultA,
+        userTargetTa: userAtaA,
+        mintTarget: mintA,
+        userWithdrawTa: userAtaB,
+        mintWithdraw: mintB
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+    }
+
+    const approvals = await Promise.all(batchSequences.map(async (batchSequence, i) => {
+      const withdrawRequestData = await program.account.withdrawRequest.fetch(batchRequests[i]);
+      const verifierHash = deriveWithdrawRequestSigningHash(
+        program.programId,
+        chainId,
+        vaultA,
+        user,
+        mintB,
+        userAtaB,
+        mintA,
+        batchSequence,
+        withdrawRequestData.shares,
+        withdrawRequestData.nav,
+        withdrawRequestData.withdrawAmount,
+      )
+      return {
+        sequence: batchSequence,
+        signatures: [{ signer: 0, signature: createWithdrawSignature(verifierKeypair, verifierHash).signature }]
+      }
+    }))
+
+    const tx = await program.methods.vaultWithdrawBatch(
+      approvals
+    )
+    .accountsStrict({
+      ...accounts,
+      keeper: payer,
+      vault: vaultA,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .remainingAccounts(batchRequests.flatMap((batchRequest) => [
+      { pubkey: batchRequest, isSigner: false, isWritable: true },
+      { pubkey: userAtaB, isSigner: false, isWritable: true },
+      { pubkey: user, isSigner: false, isWritable: true },
+    ]))
+    .signers([payerKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Settle withdraw request at the redemption epoch NAV", async () => {
+    await program.methods.vaultSetEpochMode(
+      true
+    )
+    .accountsStrict({
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const vaultData = await program.account.vault.fetch(vaultA);
+    const epochSequence = vaultData.withdrawRequestCount;
+    const epochRequest = deriveWithdrawRequestAddress(vaultA, epochSequence);
+    const redemptionEpoch = deriveRedemptionEpochAddress(vaultA, vaultData.currentEpoch);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(10_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: epochRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    await program.methods.vaultCloseRedemptionEpoch(
+      vaultData.nav
+    )
+    .accountsStrict({
+      ...accounts,
+      oracleManager: authority,
+      vault: vaultA,
+      redemptionEpoch
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // The request is signed at the settlement NAV of its epoch
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(epochRequest);
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintB,
+      userAtaB,
+      mintA,
+      epochSequence,
+      withdrawRequestData.shares,
+      vaultData.nav,
+      // Settled at a NAV and currency rate of 1 Bitcoin, the withdraw amount equals the shares
+      withdrawRequestData.shares,
+    )
+
+    const signature = createWithdrawSignature(
+      verifierKeypair,
+      verifierHash
+    )
+    await program.methods.vaultWithdraw(
+      epochSequence,
+      [{ signer: 0, signature: signature.signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: epochRequest,
+      redemptionEpoch,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    await program.methods.vaultSetEpochMode(
+      false
+    )
+    .accountsStrict({
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Switch withdraw request currency", async () => {
+    const switchSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const switchRequest = deriveWithdrawRequestAddress(vaultA, switchSequence);
+    const newSequence = switchSequence.addn(1);
+    const newRequest = deriveWithdrawRequestAddress(vaultA, newSequence);
+
+    await program.methods.vaultWithdrawRequest(
+      new BN(10_000)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: switchRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(switchRequest);
+    const verifierHash = deriveWithdrawSwitchSigningHash(
+      deriveWithdrawRequestSigningHash(
+        program.programId,
+        chainId,
+        vaultA,
+        user,
+        mintB,
+        userAtaB,
+        mintA,
+        switchSequence,
+        withdrawRequestData.shares,
+        withdrawRequestData.nav,
+        withdrawRequestData.withdrawAmount,
+      ),
+      withdrawRequestData.filledAmount,
+      mintB,
+      userAtaB
+    )
+
+    const signature = createWithdrawSignature(
+      verifierKeypair,
+      verifierHash
+    )
+    // A third party submits the switch and funds the new request
+    const userLamports = await connection.getBalance(user)
+    await program.methods.vaultSwitchWithdrawCurrency(
+      switchSequence,
+      [{ signer: 0, signature: signature.signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      payer,
+      withdrawRequest: switchRequest,
+      newWithdrawRequest: newRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaB,
+      mintWithdraw: mintB
+    })
+    .signers([payerKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    if (await connection.getBalance(user) != userLamports) {
+      throw new Error("Rent of the replaced request refunded to the user")
+    }
+
+    const newRequestData = await program.account.withdrawRequest.fetch(newRequest);
+    if (!newRequestData.shares.eq(withdrawRequestData.shares) || !newRequestData.sequence.eq(newSequence)) {
+      throw new Error("Invalid switched withdraw request")
+    }
+
+    // Cancel the switched request so the withdraw queue can be fully advanced
+    await program.methods.vaultCancelWithdrawRequest(
+      newSequence
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: newRequest,
+      vault: vaultA,
+      multisig: multisigA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Instantly redeem shares within the instant redemption cap", async () => {
+    await program.methods.vaultSetInstantRedeem(
+      50,
+      new BN(20_000),
+      3600
+    )
+    .accountsStrict({
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // The withdraw request account is only needed to allow a fallback
+    await program.methods.vaultInstantRedeem(
+      new BN(10_000),
+      new BN(9_950)
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: null,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      userWithdrawTa: userAtaB,
+      mintTarget: mintA,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Fall back to a withdraw request above the instant redemption cap", async () => {
+    const capSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const capRequest = deriveWithdrawRequestAddress(vaultA, capSequence);
+    const redeemAccounts = {
+      ...accounts,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      userWithdrawTa: userAtaB,
+      mintTarget: mintA,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    }
+
+    try {
+      await program.methods.vaultInstantRedeem(
+        new BN(15_000),
+        new BN(0)
+      )
+      .accountsStrict({
+        ...redeemAccounts,
+        withdrawRequest: null
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Withdraw request account required") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    const before = await getAccount(connection, userAtaB)
+
+    await program.methods.vaultInstantRedeem(
+      new BN(15_000),
+      new BN(0)
+    )
+    .accountsStrict({
+      ...redeemAccounts,
+      withdrawRequest: capRequest
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const after = await getAccount(connection, userAtaB)
+    if (after.amount != before.amount) {
+      throw new Error("Redemption above the cap paid out")
+    }
+    const request = await program.account.withdrawRequest.fetch(capRequest)
+    if (!request.shares.eq(new BN(15_000))) {
+      throw new Error("Invalid fallback withdraw request")
+    }
+
+    await program.methods.vaultCancelWithdrawRequest(
+      capSequence
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: capRequest,
+      vault: vaultA,
+      multisig: multisigA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Fail to set an instant redemption cap with a zero window", async () => {
+    try {
+      await program.methods.vaultSetInstantRedeem(
+        50,
+        new BN(20_000),
+        0
+      )
+      .accountsStrict({
+        ...accounts,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Invalid instant redemption window") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  })
+
+  it("Fall back to a withdraw request when instant redemption liquidity is held in reserve", async () => {
+    const reserve = (await getAccount(connection, vaultAAtaB)).amount
+
+    await program.methods.vaultSetInstantRedeemReserve(
+      mintB,
+      new BN(reserve.toString())
+    )
+    .accountsStrict({
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const fallbackSequence = (await program.account.vault.fetch(vaultA)).withdrawRequestCount;
+    const fallbackRequest = deriveWithdrawRequestAddress(vaultA, fallbackSequence);
+    const redeemAccounts = {
+      ...accounts,
+      withdrawRequest: fallbackRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      userWithdrawTa: userAtaB,
+      mintTarget: mintA,
+      mintWithdraw: mintB,
+      vaultWithdrawTa: vaultAAtaB,
+      feeReceiverTa: authorityAtaB
+    }
+    const before = await getAccount(connection, userAtaB)
+
+    // The fallback request is worth the shares net of the 5% withdraw fee
+    try {
+      await program.methods.vaultInstantRedeem(
+        new BN(5_000),
+        new BN(4_975)
+      )
+      .accountsStrict(redeemAccounts)
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Slippage exceeded") {
+        throw new Error("Unexpected error message")
+      }
+    }
+
+    await program.methods.vaultInstantRedeem(
+      new BN(5_000),
+      new BN(4_750)
+    )
+    .accountsStrict(redeemAccounts)
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const after = await getAccount(connection, userAtaB)
+    if (after.amount != before.amount) {
+      throw new Error("Reserved liquidity paid out")
+    }
+    const request = await program.account.withdrawRequest.fetch(fallbackRequest)
+    if (!request.shares.eq(new BN(5_000))) {
+      throw new Error("Invalid fallback withdraw request")
+    }
+
+    await program.methods.vaultCancelWithdrawRequest(
+      fallbackSequence
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: fallbackRequest,
+      vault: vaultA,
+      multisig: multisigA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      mintWithdraw: mintB
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    await program.methods.vaultSetInstantRedeemReserve(
+      mintB,
+      new BN(0)
+    )
+    .accountsStrict({
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+  })
+
+  it("Fail to withdraw to an address book entry before the delay", async () => {
+    await program.methods.vaultSetAddressBookDelay(
+      3600
+    )
+    .accountsStrict({
+      ...accounts,
+      admin: authority,
+      payer: authority,
+      vault: vaultA,
+      mint: mintA
+    })
+    .signers([authorityKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    await program.methods.vaultAddAddressBookEntry(
+      authorityAtaB
+    )
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const pendingRequest = deriveWithdrawRequestAddress(vaultA, (await program.account.vault.fetch(vaultA)).withdrawRequestCount);
+    try {
+      await program.methods.vaultWithdrawRequest(
+        new BN(10_000)
+      )
+      .accountsStrict({
+        ...accounts,
+        withdrawRequest: pendingRequest,
+        vault: vaultA,
+        userTargetTa: userAtaA,
+        mintTarget: mintA,
+        userWithdrawTa: authorityAtaB,
+        mintWithdraw: mintB
+      })
+      .signers([userKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+      throw new Error("This shouldn't succeed")
+    } catch(e) {
+      if (e.error.errorMessage != "SolvVault: Withdraw destination not allowed") {
+        throw new Error("Unexpected error message")
+      }
+    }
+  })
+
+  it("Deposit and withdraw a 6-decimal currency against 8-decimal shares", async () => {
+    const lamports = await getMinimumBalanceForRentExemptMint(connection);
+    let tx = new Transaction();
+    tx.instructions = [
+      SystemProgram.createAccount({
+        fromPubkey: provider.publicKey,
+        newAccountPubkey: mintC,
+        lamports,
+        space: MINT_SIZE,
+        programId: tokenProgram,
+      }),
+      createInitializeMint2Instruction(mintC, 6, provider.publicKey, null),
+      ...[[userAtaC, user], [authorityAtaC, authority], [vaultAAtaC, vaultA]].map(([ata, owner]) =>
+        createAssociatedTokenAccountIdempotentInstruction(
+          provider.publicKey,
+          ata,
+          owner,
+          mintC
+        )
+      ),
+      createMintToInstruction(mintC, userAtaC, provider.publicKey, 1_000_000),
+      createMintToInstruction(mintC, vaultAAtaC, provider.publicKey, 1_000_000),
+    ];
+    await provider.sendAndConfirm(tx, [mintCKeypair]).then(log);
+
+    await program.methods.vaultAddCurrency(
+      0,
+      ONE_BITCOIN
+    )
+      .accountsStrict({
+        ...accounts,
+        currency: mintC,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    // 1.000000 of the currency at 1 BTC per unit is worth 1.00000000 BTC of shares at the vault NAV
+    const vaultData = await program.account.vault.fetch(vaultA);
+    const sharesBefore = (await getAccount(connection, userAtaA)).amount
+    await program.methods.vaultDeposit(
+      new BN(1_000_000),
+      new BN(0)
+    )
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA,
+      multisig: multisigA,
+      userTokenTa: userAtaC,
+      userTargetTa: userAtaA,
+      treasurerTokenTa: authorityAtaC,
+      feeReceiverTargetTa: authorityAtaA,
+      userDeposit: userDepositA,
+      mintToken: mintC,
+      mintTarget: mintA,
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const shares = ONE_BITCOIN.mul(ONE_BITCOIN).div(vaultData.nav)
+    const minted = (await getAccount(connection, userAtaA)).amount - sharesBefore
+    if (minted != BigInt(shares.toString())) {
+      throw new Error("Invalid shares minted")
+    }
+
+    const decimalsSequence = vaultData.withdrawRequestCount;
+    const decimalsRequest = deriveWithdrawRequestAddress(vaultA, decimalsSequence);
+    await program.methods.vaultWithdrawRequest(
+      shares
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: decimalsRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaC,
+      mintWithdraw: mintC
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // Shares are redeemed back into 6-decimal units of the currency
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(decimalsRequest);
+    const withdrawAmount = shares.mul(vaultData.nav).muln(1_000_000).div(ONE_BITCOIN.mul(ONE_BITCOIN))
+    if (!withdrawRequestData.withdrawAmount.eq(withdrawAmount)) {
+      throw new Error("Invalid withdraw amount")
+    }
+
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintC,
+      userAtaC,
+      mintA,
+      decimalsSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+    const balanceBefore = (await getAccount(connection, userAtaC)).amount
+    await program.methods.vaultWithdraw(
+      decimalsSequence,
+      [{ signer: 0, signature: createWithdrawSignature(verifierKeypair, verifierHash).signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: decimalsRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaC,
+      mintWithdraw: mintC,
+      vaultWithdrawTa: vaultAAtaC,
+      feeReceiverTa: authorityAtaC
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawFee = withdrawAmount.muln(vaultData.withdrawFee).divn(10_000)
+    const withdrawn = (await getAccount(connection, userAtaC)).amount - balanceBefore
+    if (withdrawn != BigInt(withdrawAmount.sub(withdrawFee).toString())) {
+      throw new Error("Invalid withdrawal amount")
+    }
+  })
+
+  it("Deposit and withdraw a currency at a non 1:1 rate", async () => {
+    const lamports = await getMinimumBalanceForRentExemptMint(connection);
+    let tx = new Transaction();
+    tx.instructions = [
+      SystemProgram.createAccount({
+        fromPubkey: provider.publicKey,
+        newAccountPubkey: mintD,
+        lamports,
+        space: MINT_SIZE,
+        programId: tokenProgram,
+      }),
+      createInitializeMint2Instruction(mintD, 8, provider.publicKey, null),
+      ...[[userAtaD, user], [authorityAtaD, authority], [vaultAAtaD, vaultA]].map(([ata, owner]) =>
+        createAssociatedTokenAccountIdempotentInstruction(
+          provider.publicKey,
+          ata,
+          owner,
+          mintD
+        )
+      ),
+      createMintToInstruction(mintD, userAtaD, provider.publicKey, 1_000_000),
+      createMintToInstruction(mintD, vaultAAtaD, provider.publicKey, 1_000_000),
+    ];
+    await provider.sendAndConfirm(tx, [mintDKeypair]).then(log);
+
+    // One whole unit of the currency is worth 0.5 BTC
+    const rate = ONE_BITCOIN.divn(2)
+    await program.methods.vaultAddCurrency(
+      0,
+      rate
+    )
+      .accountsStrict({
+        ...accounts,
+        currency: mintD,
+        admin: authority,
+        payer: authority,
+        vault: vaultA,
+        mint: mintA
+      })
+      .signers([authorityKeypair])
+      .rpc()
+      .then(confirm)
+      .then(log)
+
+    const vaultData = await program.account.vault.fetch(vaultA);
+    const amount = new BN(1_000_000)
+    const sharesBefore = (await getAccount(connection, userAtaA)).amount
+    await program.methods.vaultDeposit(
+      amount,
+      new BN(0)
+    )
+    .accountsStrict({
+      ...accounts,
+      vault: vaultA,
+      multisig: multisigA,
+      userTokenTa: userAtaD,
+      userTargetTa: userAtaA,
+      treasurerTokenTa: authorityAtaD,
+      feeReceiverTargetTa: authorityAtaA,
+      // No per-user cap is set, so shares need not be tracked
+      userDeposit: null,
+      mintToken: mintD,
+      mintTarget: mintA,
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    // amount * rate / nav, half the shares a 1:1 currency would mint
+    const shares = amount.mul(rate).div(vaultData.nav)
+    const minted = (await getAccount(connection, userAtaA)).amount - sharesBefore
+    if (minted != BigInt(shares.toString())) {
+      throw new Error("Invalid shares minted")
+    }
+
+    const rateSequence = vaultData.withdrawRequestCount;
+    const rateRequest = deriveWithdrawRequestAddress(vaultA, rateSequence);
+    await program.methods.vaultWithdrawRequest(
+      shares
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: rateRequest,
+      vault: vaultA,
+      userTargetTa: userAtaA,
+      mintTarget: mintA,
+      userWithdrawTa: userAtaD,
+      mintWithdraw: mintD
     })
     .signers([userKeypair])
     .rpc()
     .then(confirm)
     .then(log)
+
+    // shares * nav / rate, twice the units a 1:1 currency would pay
+    const withdrawRequestData = await program.account.withdrawRequest.fetch(rateRequest);
+    const withdrawAmount = shares.mul(vaultData.nav).div(rate)
+    if (!withdrawRequestData.withdrawAmount.eq(withdrawAmount)) {
+      throw new Error("Invalid withdraw amount")
+    }
+
+    const verifierHash = deriveWithdrawRequestSigningHash(
+      program.programId,
+      chainId,
+      vaultA,
+      user,
+      mintD,
+      userAtaD,
+      mintA,
+      rateSequence,
+      withdrawRequestData.shares,
+      withdrawRequestData.nav,
+      withdrawRequestData.withdrawAmount,
+    )
+    const balanceBefore = (await getAccount(connection, userAtaD)).amount
+    await program.methods.vaultWithdraw(
+      rateSequence,
+      [{ signer: 0, signature: createWithdrawSignature(verifierKeypair, verifierHash).signature }]
+    )
+    .accountsStrict({
+      ...accounts,
+      withdrawRequest: rateRequest,
+      vault: vaultA,
+      userWithdrawTa: userAtaD,
+      mintWithdraw: mintD,
+      vaultWithdrawTa: vaultAAtaD,
+      feeReceiverTa: authorityAtaD
+    })
+    .signers([userKeypair])
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const withdrawFee = withdrawAmount.muln(vaultData.withdrawFee).divn(10_000)
+    const withdrawn = (await getAccount(connection, userAtaD)).amount - balanceBefore
+    if (withdrawn != BigInt(withdrawAmount.sub(withdrawFee).toString())) {
+      throw new Error("Invalid withdrawal amount")
+    }
+  })
+
+  it("Advance withdraw queue past completed requests", async () => {
+    const vaultData = await program.account.vault.fetch(vaultA);
+    const completedRequests = Array.from(
+      { length: vaultData.withdrawRequestCount.sub(vaultData.withdrawQueueHead).toNumber() },
+      (_, i) => deriveWithdrawRequestAddress(vaultA, vaultData.withdrawQueueHead.addn(i))
+    )
+
+    await program.methods.vaultAdvanceWithdrawQueue()
+    .accountsStrict({
+      vault: vaultA
+    })
+    .remainingAccounts(completedRequests.map((completedRequest) => (
+      { pubkey: completedRequest, isSigner: false, isWritable: false }
+    )))
+    .rpc()
+    .then(confirm)
+    .then(log)
+
+    const advancedVaultData = await program.account.vault.fetch(vaultA);
+    if (!advancedVaultData.withdrawQueueHead.eq(advancedVaultData.withdrawRequestCount)) {
+      throw new Error("Invalid withdraw queue head")
+    }
   })
 });